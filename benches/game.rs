@@ -0,0 +1,108 @@
+//! Timings for win detection, moves, flips and the bot, run with
+//! `cargo bench --features bench`.
+
+use std::time::Duration;
+
+use c4::{Board, Bot, Classic, Colour, Column, Difficulty, Game, Gravity};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// A full board with no line on it, drawn in two wide vertical stripes that
+/// alternate every row.
+fn full_board<const W: usize, const H: usize>() -> Board<W, H> {
+    let mut rows = [[None; W]; H];
+
+    for (row, cells) in rows.iter_mut().enumerate() {
+        for (column, cell) in cells.iter_mut().enumerate() {
+            *cell = Some(if (column / 2 + row) % 2 == 0 {
+                Colour::Red
+            } else {
+                Colour::Yellow
+            });
+        }
+    }
+
+    Board::from_grid(&rows, Gravity::Down).unwrap()
+}
+
+fn full_game<const W: usize, const H: usize>() -> Game<W, H> {
+    Game::builder().position(full_board()).build()
+}
+
+/// Plays the columns in `moves` from a new game.
+fn play(moves: &[usize]) -> Classic {
+    let mut game = Classic::new(Colour::Red, false);
+
+    for &idx in moves {
+        game.make_move(Column::new(idx).unwrap()).unwrap();
+    }
+
+    game
+}
+
+// fills `full_board::<7, 6>` two rows at a time, ending in a tie
+const MOVES: &[usize] = &[
+    0, 2, 1, 3, 4, 6, 5, 0, 2, 1, 3, 4, 6, 5, 0, 2, 1, 3, 4, 6, 5, 0, 2, 1, 3, 4, 6, 5, 0, 2, 1, 3,
+    4, 6, 5, 0, 2, 1, 3, 4, 6, 5,
+];
+
+fn win_detection(c: &mut Criterion) {
+    let classic = full_game::<7, 6>();
+    c.bench_function("check_win_all 7x6", |b| {
+        b.iter(|| c4::bench::check_win_all(black_box(&classic)))
+    });
+
+    // too big for a bitboard, so every cell is scanned
+    let large = full_game::<12, 10>();
+    c.bench_function("check_win_all 12x10", |b| {
+        b.iter(|| c4::bench::check_win_all(black_box(&large)))
+    });
+}
+
+/// Bitboards against the scan they replaced, on the same positions part way
+/// through filling the board and at its end.
+fn bitboard_vs_scan(c: &mut Criterion) {
+    let positions = [
+        ("opening", play(&MOVES[..8])),
+        ("middlegame", play(&MOVES[..24])),
+        ("full", full_game::<7, 6>()),
+    ];
+
+    let mut group = c.benchmark_group("bitboard vs scan");
+    for (name, game) in &positions {
+        group.bench_function(format!("bitboard {}", name), |b| {
+            b.iter(|| c4::bench::check_win_all(black_box(game)))
+        });
+        group.bench_function(format!("scan {}", name), |b| {
+            b.iter(|| c4::bench::scan_win_all(black_box(game)))
+        });
+    }
+    group.finish();
+}
+
+fn moves(c: &mut Criterion) {
+    c.bench_function("make_move", |b| b.iter(|| play(black_box(MOVES))));
+
+    let mut game = full_game::<7, 6>();
+    c.bench_function("flip", |b| b.iter(|| c4::bench::flip(black_box(&mut game))));
+}
+
+fn search(c: &mut Criterion) {
+    // flipping games aren't in the opening book, so the bot has to search
+    let game = Classic::builder()
+        .flipping(true)
+        .position(play(&MOVES[..14]).state().clone())
+        .build();
+
+    let mut group = c.benchmark_group("bot");
+    group.sample_size(10);
+    group.bench_function("medium search", |b| {
+        b.iter(|| {
+            let mut bot = Bot::with_seed(game.current_colour(), Duration::from_secs(1), 0);
+            bot.select_move(black_box(&game), Difficulty::Medium)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, win_detection, bitboard_vs_scan, moves, search);
+criterion_main!(benches);
@@ -0,0 +1,12 @@
+#![no_main]
+
+use c4::Script;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|script: Script| {
+    script.run::<7, 6>();
+    // more rows than columns, so sideways gravity has lanes that can't be
+    // dropped into, and the other way around
+    script.run::<3, 8>();
+    script.run::<9, 5>();
+});
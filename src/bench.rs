@@ -0,0 +1,20 @@
+//! Internals exposed to the benchmarks with the `bench` feature. Nothing here
+//! is part of the public API.
+
+use crate::{Game, Winner};
+
+/// Scans the whole board for a line, as after a flip or pop.
+pub fn check_win_all<const W: usize, const H: usize>(game: &Game<W, H>) -> Option<Winner> {
+    game.check_win_all()
+}
+
+/// `check_win_all` scanning out from every disc, as it did before
+/// bitboards, to compare them against.
+pub fn scan_win_all<const W: usize, const H: usize>(game: &Game<W, H>) -> Option<Winner> {
+    game.scan_win_all()
+}
+
+/// Flips gravity without making a move.
+pub fn flip<const W: usize, const H: usize>(game: &mut Game<W, H>) {
+    game.flip();
+}
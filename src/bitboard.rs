@@ -0,0 +1,150 @@
+//! Win detection on one `u64` per colour.
+//!
+//! Cell `(column, row)` is bit `column * (H + 1) + row`, leaving an always
+//! empty row above each column so that lines can't wrap from the top of one
+//! column into the bottom of the next. A line of `n` discs in a direction
+//! whose neighbouring cells are `s` bits apart is then found by and-ing the
+//! board with itself shifted by `s`, `2s`, ... `(n - 1)s`.
+
+use crate::{Board, Colour, Column, Winner};
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    pub(crate) const BITBOARD_FITS: bool = W * (H + 1) <= 64;
+
+    /// The discs of `colour`, only meaningful if `BITBOARD_FITS`.
+    pub(crate) fn bitboard(&self, colour: Colour) -> u64 {
+        self.bitboards[colour_idx(colour)]
+    }
+
+    /// Keeps the bitboards in step with the cell `set_cell` just changed.
+    pub(crate) fn set_bitboard_cell(&mut self, column: Column<W>, row: u8, disc: Option<Colour>) {
+        if !Self::BITBOARD_FITS {
+            return;
+        }
+
+        let bit = 1 << (column.to_idx() * (H + 1) + row as usize);
+        for bits in &mut self.bitboards {
+            *bits &= !bit;
+        }
+        if let Some(colour) = disc {
+            self.bitboards[colour_idx(colour)] |= bit;
+        }
+    }
+
+    /// `bitboard` worked out from the cells, for checking it.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn scan_bitboard(&self, colour: Colour) -> u64 {
+        if !Self::BITBOARD_FITS {
+            return 0;
+        }
+
+        self.iter_cells()
+            .filter(|&(_, _, disc)| disc == Some(colour))
+            .fold(0, |bits, (column, row, _)| {
+                bits | 1 << (column.to_idx() * (H + 1) + row as usize)
+            })
+    }
+}
+
+fn colour_idx(colour: Colour) -> usize {
+    match colour {
+        Colour::Red => 0,
+        Colour::Yellow => 1,
+    }
+}
+
+/// A mask of the first cell of every `len` long line in `bits`.
+fn line_starts<const H: usize>(bits: u64, len: usize) -> u64 {
+    // vertical, horizontal and both diagonals
+    let shifts = [1, H + 1, H, H + 2];
+
+    shifts
+        .iter()
+        .map(|&shift| {
+            (1..len).fold(bits, |acc, i| {
+                acc & bits.checked_shr((shift * i) as u32).unwrap_or(0)
+            })
+        })
+        .fold(0, |acc, starts| acc | starts)
+}
+
+/// The colour with a line of `len` discs on the board.
+///
+/// If both colours have a line it's a tie.
+pub(crate) fn find_win<const W: usize, const H: usize>(
+    board: &Board<W, H>,
+    len: usize,
+) -> Option<Winner> {
+    let red = line_starts::<H>(board.bitboard(Colour::Red), len);
+    let yellow = line_starts::<H>(board.bitboard(Colour::Yellow), len);
+
+    match (red, yellow) {
+        (0, 0) => None,
+        (_, 0) => Some(Winner::Red),
+        (0, _) => Some(Winner::Yellow),
+        _ => Some(Winner::Tie),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use rand::prelude::*;
+
+    use super::*;
+    use crate::Game;
+
+    /// Columns stacked to random heights with random discs, which unlike a
+    /// real game can hold any number of lines for both colours.
+    fn random_board<const W: usize, const H: usize>(rng: &mut StdRng) -> Board<W, H> {
+        let mut board = Board::new();
+
+        for column in Column::all() {
+            for _ in 0..rng.gen_range(0..=H) {
+                let colour = if rng.gen() {
+                    Colour::Red
+                } else {
+                    Colour::Yellow
+                };
+                board.place_on_column(column, colour);
+            }
+        }
+
+        board
+    }
+
+    fn agrees_with_scanning<const W: usize, const H: usize>(rng: &mut StdRng) {
+        assert!(Board::<W, H>::BITBOARD_FITS);
+
+        for _ in 0..1000 {
+            let board = random_board::<W, H>(rng);
+            for &colour in Colour::all() {
+                assert_eq!(board.bitboard(colour), board.scan_bitboard(colour));
+            }
+
+            for len in 2..=5 {
+                let game = Game::builder()
+                    .win_length(len)
+                    .position(board.clone())
+                    .build();
+
+                assert_eq!(
+                    find_win(&board, len),
+                    game.scan_win_all(),
+                    "connect {}\n{}",
+                    len,
+                    board
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bitboard_agrees_with_scanning() {
+        let mut rng = StdRng::seed_from_u64(23);
+
+        agrees_with_scanning::<7, 6>(&mut rng);
+        agrees_with_scanning::<5, 4>(&mut rng);
+        // exactly the 64 bits a bitboard holds
+        agrees_with_scanning::<8, 7>(&mut rng);
+    }
+}
@@ -0,0 +1,144 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use crate::{Board, Column, Game, BOARD_HEIGHT, BOARD_WIDTH, WIN_LENGTH};
+
+const CLASSIC: &str = include_str!("openings.txt");
+
+/// Known good moves for early positions, looked up by `Board::canonical_key`
+/// so a position and its mirror share an entry.
+#[derive(Debug, Clone)]
+pub struct OpeningBook<const W: usize, const H: usize> {
+    // shared, as every bot starts with a copy of the classic book
+    moves: Arc<HashMap<u64, Column<W>>>,
+    /// How long a line the moves are good for.
+    win_length: usize,
+}
+
+impl<const W: usize, const H: usize> Default for OpeningBook<W, H> {
+    fn default() -> Self {
+        Self {
+            moves: Default::default(),
+            win_length: WIN_LENGTH,
+        }
+    }
+}
+
+pub(crate) fn mirror_column<const W: usize>(column: Column<W>) -> Column<W> {
+    Column(W - 1 - column.to_idx())
+}
+
+impl<const W: usize, const H: usize> OpeningBook<W, H> {
+    /// The book shipped with the crate, which only covers classic boards
+    /// and lines of four. It's only read the first time it's needed.
+    pub fn classic() -> Self {
+        static CLASSIC_MOVES: OnceLock<Arc<HashMap<u64, Column<BOARD_WIDTH>>>> = OnceLock::new();
+
+        let moves: Arc<dyn Any + Send + Sync> = CLASSIC_MOVES
+            .get_or_init(|| {
+                OpeningBook::<BOARD_WIDTH, BOARD_HEIGHT>::parse(CLASSIC)
+                    .expect("the built in opening book is invalid")
+                    .moves
+            })
+            .clone();
+
+        // only the same type when `W` is the classic width
+        match moves.downcast() {
+            Ok(moves) if H == BOARD_HEIGHT => Self {
+                moves,
+                ..Self::default()
+            },
+            _ => Self::default(),
+        }
+    }
+
+    /// Sets the length of line the book's moves are for, which is four
+    /// unless set. Games played to any other length never use the book.
+    pub fn win_length(mut self, length: usize) -> Self {
+        self.win_length = length;
+        self
+    }
+
+    /// Reads a book with a position in board notation and the move to play
+    /// in it on each line. Blank lines and lines starting with `#` are
+    /// skipped.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut book = Self::default();
+
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (position, column) = line
+                .split_once(' ')
+                .ok_or_else(|| format!("line {} is missing a move", idx + 1))?;
+            let board = Board::from_notation(position)
+                .map_err(|e| format!("line {} is invalid: {}", idx + 1, e))?;
+            let column = column
+                .parse()
+                .map_err(|e| format!("line {} is invalid: {}", idx + 1, e))?;
+
+            book.insert(&board, column);
+        }
+
+        Ok(book)
+    }
+
+    pub fn insert(&mut self, board: &Board<W, H>, column: Column<W>) {
+        let key = board.canonical_key();
+
+        // moves are stored as they'd be played on whichever of the board and
+        // its mirror gives the key
+        let column = if board.key() == key {
+            column
+        } else {
+            mirror_column(column)
+        };
+
+        Arc::make_mut(&mut self.moves).insert(key, column);
+    }
+
+    /// The booked move for the current position, never given for games that
+    /// flip gravity or are played to another length of line.
+    pub fn lookup(&self, game: &Game<W, H>) -> Option<Column<W>> {
+        if game.flips() || game.is_finished() || game.win_length != self.win_length {
+            return None;
+        }
+
+        let key = game.state.canonical_key();
+        let column = *self.moves.get(&key)?;
+
+        let column = if game.state.key() == key {
+            column
+        } else {
+            mirror_column(column)
+        };
+
+        Some(column).filter(|&c| !game.state.column_full(c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Classic, Colour};
+
+    #[test]
+    fn books_are_only_used_for_their_win_length() {
+        let book = OpeningBook::classic();
+
+        let game = Classic::new(Colour::Red, false);
+        assert_eq!(book.lookup(&game), Some(Column::D));
+
+        let game = Classic::with_win_length(Colour::Red, false, 5);
+        assert_eq!(book.lookup(&game), None);
+        assert_eq!(
+            OpeningBook::<7, 6>::classic().win_length(5).lookup(&game),
+            Some(Column::D)
+        );
+    }
+}
@@ -0,0 +1,593 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use rand::prelude::*;
+
+use crate::{AnalysisCache, Colour, Column, Fitness, Game, OpeningBook, Tablebase};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    /// A shallow search that sometimes plays a random move instead.
+    Easy,
+    /// A fixed depth search, ignoring the think time.
+    Medium,
+    /// Searches for the whole think time.
+    Hard,
+}
+
+impl Difficulty {
+    const EASY_BLUNDER_CHANCE: f64 = 0.3;
+    const EASY_DEPTH: u32 = 2;
+    const MEDIUM_DEPTH: u32 = 4;
+}
+
+// how far `bot_analysis` follows the expected line, and how deeply it
+// searches for each move after the first
+const LINE_LENGTH: usize = 4;
+const LINE_DEPTH: u32 = 4;
+
+// a pondered answer is played without searching again once it was found
+// this deep
+const PONDER_DEPTH: u32 = 6;
+
+/// The move the current player's bot would make, the line of play it expects
+/// to follow starting with that move, and the fitness at the end of the line.
+pub fn bot_analysis<const W: usize, const H: usize>(
+    game: &Game<W, H>,
+    think_time: Duration,
+) -> Option<(Column<W>, Vec<Column<W>>, Fitness)> {
+    let chosen = rubot::Bot::new(game.current_colour()).select(game, think_time)?;
+    let (line, fitness) = expected_line(game, chosen)?;
+
+    Some((chosen, line, fitness))
+}
+
+/// The line of play a bot expects to follow `chosen`, starting with it, and
+/// the fitness for the current player at the end of the line.
+///
+/// `rubot` only reports the move it picks, so the rest of the line comes from
+/// shallower searches of each position along it.
+pub fn expected_line<const W: usize, const H: usize>(
+    game: &Game<W, H>,
+    chosen: Column<W>,
+) -> Option<(Vec<Column<W>>, Fitness)> {
+    let colour = game.current_colour();
+    let mut line = vec![chosen];
+    let mut position = game.with_move(chosen).ok()?;
+
+    while line.len() < LINE_LENGTH && !position.is_finished() {
+        let mut bot = rubot::Bot::new(position.current_colour());
+
+        match bot.select(&position, rubot::Depth(LINE_DEPTH)) {
+            Some(next) if position.make_move(next).is_ok() => line.push(next),
+            _ => break,
+        }
+    }
+
+    Some((line, position.evaluate(colour)))
+}
+
+impl<const W: usize, const H: usize> Game<W, H> {
+    /// The move a bot searching for `think_time` would make for the current
+    /// player, without making it. Booked openings are suggested straight away.
+    pub fn suggest(&self, think_time: Duration) -> Option<Column<W>> {
+        if self.is_finished() {
+            return None;
+        }
+
+        if let Some(column) = OpeningBook::classic().lookup(self) {
+            return Some(column);
+        }
+
+        rubot::Bot::new(self.current_colour()).select(self, think_time)
+    }
+}
+
+/// How a bot found the move it chose. Moves that weren't searched for, like
+/// booked openings, have no nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchStats<const W: usize> {
+    pub chosen: Column<W>,
+    pub nodes: u64,
+    pub depth: usize,
+    pub elapsed: Duration,
+}
+
+impl<const W: usize> std::fmt::Display for SearchStats<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} after {} nodes to depth {} in {:.2?}",
+            self.chosen, self.nodes, self.depth, self.elapsed
+        )
+    }
+}
+
+/// Stops a search at `depth`, or as soon as `stop` is set.
+struct Cancellable<'a> {
+    stop: &'a AtomicBool,
+    depth: u32,
+}
+
+impl<'a> rubot::RunCondition for Cancellable<'a> {
+    fn step(&mut self) -> bool {
+        !self.stop.load(Ordering::Relaxed)
+    }
+
+    fn depth(&mut self, depth: u32) -> bool {
+        self.step() && depth <= self.depth
+    }
+}
+
+/// A search on another thread of the answer to each move the opponent might
+/// make, started by `Bot::ponder`.
+struct Ponder<const W: usize, const H: usize> {
+    game: Game<W, H>,
+    stop: Arc<AtomicBool>,
+    /// The answer to each reply and how deep it was searched.
+    answers: Arc<Mutex<HashMap<Column<W>, (Column<W>, u32)>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<const W: usize, const H: usize> Ponder<W, H> {
+    fn start(game: &Game<W, H>, colour: Colour, tie_break: u64) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let answers = Arc::new(Mutex::new(HashMap::new()));
+
+        let handle = {
+            let (mut game, stop, answers) = (game.clone(), stop.clone(), answers.clone());
+            game.tie_break = tie_break;
+
+            std::thread::spawn(move || {
+                let replies = game
+                    .state()
+                    .ordered_columns()
+                    .into_iter()
+                    .filter_map(|reply| Some((reply, game.with_move(reply).ok()?)))
+                    .filter(|(_, position)| !position.is_finished())
+                    .collect::<Vec<_>>();
+
+                // deepen every reply together, so a quick opponent still finds
+                // an answer waiting for the moves they were likely to make
+                for depth in 1.. {
+                    for (reply, position) in &replies {
+                        let condition = Cancellable { stop: &stop, depth };
+                        let answer = rubot::Bot::new(colour).select(position, condition);
+
+                        // a search that was cut short might not have finished
+                        // this depth
+                        if stop.load(Ordering::Relaxed) {
+                            return;
+                        }
+
+                        if let Some(answer) = answer {
+                            answers.lock().unwrap().insert(*reply, (answer, depth));
+                        }
+                    }
+
+                    if replies.is_empty() || depth as usize >= W * H {
+                        return;
+                    }
+                }
+            })
+        };
+
+        Self {
+            game: game.clone(),
+            stop,
+            answers,
+            handle: Some(handle),
+        }
+    }
+
+    fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
+    }
+
+    /// Stops pondering, giving the answer found to `game` and how deeply it
+    /// was searched, if `game` follows on from a reply that was pondered.
+    fn commit(mut self, game: &Game<W, H>) -> Option<(Column<W>, u32)> {
+        self.stop();
+
+        let reply = self
+            .game
+            .legal_moves()
+            .into_iter()
+            .find(|&reply| self.game.with_move(reply).ok().as_ref() == Some(game))?;
+
+        let answers = self.answers.lock().unwrap();
+        answers.get(&reply).copied()
+    }
+}
+
+impl<const W: usize, const H: usize> Drop for Ponder<W, H> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A `rubot::Bot` playing one colour at a chosen strength.
+pub struct Bot<const W: usize, const H: usize> {
+    bot: rubot::Bot<Game<W, H>>,
+    colour: Colour,
+    think_time: Duration,
+    rng: StdRng,
+    book: OpeningBook<W, H>,
+    ponder: Option<Ponder<W, H>>,
+    cache: Option<AnalysisCache<W>>,
+    tablebase: Option<Arc<Tablebase<W, H>>>,
+    /// Decides which of two equally good moves is played, so that seeded
+    /// bots searching to a fixed depth always play the same way.
+    tie_break: u64,
+}
+
+impl<const W: usize, const H: usize> Bot<W, H> {
+    pub fn new(colour: Colour, think_time: Duration) -> Self {
+        Self::with_rng(colour, think_time, StdRng::from_entropy())
+    }
+
+    /// A bot whose blunders and choices between equally good moves are
+    /// decided by `seed`, so that it plays the same way every time unless
+    /// its search depth is set by the think time.
+    pub fn with_seed(colour: Colour, think_time: Duration, seed: u64) -> Self {
+        Self::with_rng(colour, think_time, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(colour: Colour, think_time: Duration, mut rng: StdRng) -> Self {
+        Self {
+            tie_break: rng.gen(),
+            bot: rubot::Bot::new(colour),
+            colour,
+            think_time,
+            rng,
+            book: OpeningBook::classic(),
+            ponder: None,
+            cache: None,
+            tablebase: None,
+        }
+    }
+
+    /// How long `Hard` searches for from the next move on, eg. to fit a
+    /// clock that's running down.
+    pub fn set_think_time(&mut self, think_time: Duration) {
+        self.think_time = think_time;
+    }
+
+    /// Remembers the moves this bot searches for in `cache`, and plays
+    /// moves found there that were searched at least as deeply as it would
+    /// search. Bots sharing a cache should play at the same think time, as
+    /// a `Hard` bot takes any move found deep enough to have been pondered.
+    pub fn use_cache(&mut self, cache: AnalysisCache<W>) {
+        self.cache = Some(cache);
+    }
+
+    /// Plays the perfect move from `tablebase` in every position it covers,
+    /// unless `Easy`.
+    pub fn use_tablebase(&mut self, tablebase: Arc<Tablebase<W, H>>) {
+        self.tablebase = Some(tablebase);
+    }
+
+    /// Starts searching for answers to the opponent's replies in `game`
+    /// while they think, for the next `select_move` to use. Only `Hard`
+    /// makes use of it.
+    pub fn ponder(&mut self, game: &Game<W, H>) {
+        // dropping a search stops it
+        self.ponder = None;
+
+        if !game.is_finished() && game.current_colour() != self.colour {
+            self.ponder = Some(Ponder::start(game, self.colour, self.tie_break));
+        }
+    }
+
+    /// The answer pondering found to `game`, if it searched that deep
+    /// enough to be played straight away. Pondering stops either way.
+    pub fn take_pondered(&mut self, game: &Game<W, H>) -> Option<Column<W>> {
+        self.take_pondered_with_depth(game)
+            .map(|(column, _)| column)
+    }
+
+    fn take_pondered_with_depth(&mut self, game: &Game<W, H>) -> Option<(Column<W>, u32)> {
+        match self.ponder.take()?.commit(game) {
+            Some((column, depth)) if depth >= PONDER_DEPTH => Some((column, depth)),
+            _ => None,
+        }
+    }
+
+    /// Whether to swap sides when `game` allows it, which this bot does
+    /// when the first move looks better for the side that made it.
+    pub fn should_swap(&self, game: &Game<W, H>) -> bool {
+        game.can_swap() && game.evaluate(game.current_colour().invert()) > Fitness::TIE
+    }
+
+    pub fn select_move(&mut self, game: &Game<W, H>, difficulty: Difficulty) -> Option<Column<W>> {
+        self.select_move_with_stats(game, difficulty)
+            .map(|stats| stats.chosen)
+    }
+
+    /// Like `select_move`, along with how the move was found.
+    pub fn select_move_with_stats(
+        &mut self,
+        game: &Game<W, H>,
+        difficulty: Difficulty,
+    ) -> Option<SearchStats<W>> {
+        let start = Instant::now();
+        let unsearched = |chosen, depth| SearchStats {
+            chosen,
+            nodes: 0,
+            depth,
+            elapsed: start.elapsed(),
+        };
+
+        let pondered = self.take_pondered_with_depth(game);
+
+        if difficulty != Difficulty::Easy {
+            let solved = self.tablebase.as_ref().and_then(|t| t.best_move(game));
+            if let Some(column) = solved.or_else(|| self.book.lookup(game)) {
+                return Some(unsearched(column, 0));
+            }
+        }
+
+        match difficulty {
+            Difficulty::Easy => {
+                if self.rng.gen_bool(Difficulty::EASY_BLUNDER_CHANCE) {
+                    let column = game.legal_moves().choose(&mut self.rng).copied()?;
+                    Some(unsearched(column, 0))
+                } else {
+                    let depth = Difficulty::EASY_DEPTH;
+                    self.search(game, depth, rubot::Depth(depth))
+                }
+            }
+            Difficulty::Medium => {
+                let depth = Difficulty::MEDIUM_DEPTH;
+                self.search(game, depth, rubot::Depth(depth))
+            }
+            Difficulty::Hard => match pondered {
+                Some((column, depth)) => Some(unsearched(column, depth as usize)),
+                None => self.search(game, PONDER_DEPTH, self.think_time),
+            },
+        }
+    }
+
+    /// Searches `game` until `condition` stops it, unless the cache has a
+    /// move for it searched at least `depth` deep.
+    fn search(
+        &mut self,
+        game: &Game<W, H>,
+        depth: u32,
+        condition: impl rubot::IntoRunCondition,
+    ) -> Option<SearchStats<W>> {
+        let start = Instant::now();
+
+        if let Some(cache) = &self.cache {
+            if let Some((chosen, depth)) = cache.lookup(game, depth) {
+                return Some(SearchStats {
+                    chosen,
+                    nodes: 0,
+                    depth: depth as usize,
+                    elapsed: start.elapsed(),
+                });
+            }
+        }
+
+        let mut seeded = game.clone();
+        seeded.tie_break = self.tie_break;
+
+        let mut logger = rubot::Logger::new(condition);
+        let chosen = self.bot.select(&seeded, &mut logger)?;
+
+        if let Some(cache) = &self.cache {
+            cache.insert(game, chosen, logger.depth());
+        }
+
+        Some(SearchStats {
+            chosen,
+            nodes: logger.steps() as u64,
+            depth: logger.depth() as usize,
+            elapsed: logger.duration(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+    use crate::Classic;
+
+    fn play(first: Colour, moves: &str) -> Classic {
+        let mut game = Classic::new(first, false);
+        for column in moves.chars() {
+            game.make_move(Column::try_from(column).unwrap()).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn bots_block_open_threes() {
+        // red's three in D, and along the bottom with B taken
+        for (moves, block) in [("DADBD", Column::D), ("CBDAE", Column::F)] {
+            let game = play(Colour::Red, moves);
+
+            for seed in 0..8 {
+                let mut bot = Bot::with_seed(Colour::Yellow, Duration::from_secs(1), seed);
+                assert_eq!(
+                    bot.select_move(&game, Difficulty::Medium),
+                    Some(block),
+                    "{} with seed {}",
+                    moves,
+                    seed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bots_take_the_quickest_win() {
+        // A wins now, while D wins two moves later from both ends of C D E
+        let game = play(Colour::Red, "CCEEAGAGAC");
+        assert!(game.with_move(Column::D).unwrap().evaluate(Colour::Red) > Fitness::TIE);
+
+        for seed in 0..8 {
+            let mut bot = Bot::with_seed(Colour::Red, Duration::from_secs(1), seed);
+            assert_eq!(
+                bot.select_move(&game, Difficulty::Medium),
+                Some(Column::A),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn easy_bots_sometimes_miss_the_best_move() {
+        // A wins straight away, which the depth 2 search always finds
+        let game = play(Colour::Red, "CCEEAGAGAC");
+
+        let mut bot = Bot::with_seed(Colour::Red, Duration::from_secs(1), 14);
+        let moves = (0..20)
+            .map(|_| bot.select_move(&game, Difficulty::Easy).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(moves.contains(&Column::A), "{:?}", moves);
+        assert!(
+            moves.iter().any(|&column| column != Column::A),
+            "{:?}",
+            moves
+        );
+    }
+
+    #[test]
+    fn searches_open_in_the_centre() {
+        // rubot on its own, without the opening book
+        let game = Classic::new(Colour::Red, false);
+
+        for depth in 1..=4 {
+            let mut bot = rubot::Bot::new(Colour::Red);
+            assert_eq!(
+                bot.select(&game, rubot::Depth(depth)),
+                Some(Column::D),
+                "depth {}",
+                depth
+            );
+        }
+    }
+
+    #[test]
+    fn expected_lines_start_with_the_chosen_move() {
+        let game = play(Colour::Red, "DDC");
+
+        let (chosen, line, _) = bot_analysis(&game, Duration::from_millis(100)).unwrap();
+        assert_eq!(line.first(), Some(&chosen));
+        assert!(line.len() <= LINE_LENGTH);
+
+        // the line stops at a win
+        let game = play(Colour::Red, "CCEEAGAGAC");
+        let (line, fitness) = expected_line(&game, Column::A).unwrap();
+        assert_eq!(line, vec![Column::A]);
+        assert!(fitness > Fitness::TIE);
+    }
+
+    #[test]
+    fn suggestions_take_a_forced_win() {
+        let game = play(Colour::Red, "CCEEAGAGAC");
+        assert_eq!(game.suggest(Duration::from_millis(200)), Some(Column::A));
+
+        let game = game.with_move(Column::A).unwrap();
+        assert_eq!(game.suggest(Duration::from_millis(200)), None);
+    }
+
+    #[test]
+    fn pondered_answers_are_legal() {
+        // red's bot has moved, and ponders while yellow thinks
+        let game = play(Colour::Red, "DDC");
+        let mut bot = Bot::with_seed(Colour::Red, Duration::from_millis(100), 55);
+        bot.ponder(&game);
+        std::thread::sleep(Duration::from_millis(200));
+
+        let after = game.with_move(Column::C).unwrap();
+        let (answer, depth) = bot.ponder.take().unwrap().commit(&after).unwrap();
+        assert!(after.legal_moves().contains(&answer));
+        assert!(depth >= 1);
+
+        // committing to a position that wasn't a reply finds nothing
+        bot.ponder(&game);
+        assert_eq!(bot.take_pondered(&play(Colour::Red, "AAA")), None);
+
+        bot.ponder(&game);
+        let chosen = bot.select_move(&after, Difficulty::Hard).unwrap();
+        assert!(after.legal_moves().contains(&chosen));
+    }
+
+    #[test]
+    fn search_stats_describe_a_legal_move() {
+        let game = play(Colour::Red, "DDCE");
+
+        let mut bot = Bot::with_seed(Colour::Red, Duration::from_millis(50), 58);
+        for difficulty in [Difficulty::Medium, Difficulty::Hard] {
+            let stats = bot.select_move_with_stats(&game, difficulty).unwrap();
+
+            assert!(game.legal_moves().contains(&stats.chosen));
+            assert!(stats.elapsed > Duration::ZERO);
+            assert!(stats.nodes > 0);
+            assert!(stats.depth > 0);
+        }
+
+        // booked openings aren't searched for
+        let stats = bot
+            .select_move_with_stats(&Classic::new(Colour::Red, false), Difficulty::Medium)
+            .unwrap();
+        assert_eq!(stats.nodes, 0);
+    }
+
+    #[test]
+    fn seeds_decide_between_equal_moves() {
+        // with D full the position is its own mirror image, so C and E
+        // are always as good as each other
+        let game = play(Colour::Red, "DDDDDD");
+
+        let choose = |seed| {
+            let mut bot = Bot::with_seed(Colour::Red, Duration::from_secs(1), seed);
+            bot.select_move(&game, Difficulty::Medium).unwrap()
+        };
+
+        for seed in 0..2 {
+            assert_eq!(choose(seed), choose(seed), "seed {}", seed);
+        }
+
+        let chosen = (0..8).map(choose).collect::<std::collections::HashSet<_>>();
+        assert!(chosen.len() > 1, "{:?}", chosen);
+    }
+
+    #[test]
+    fn bots_settle_for_a_tie_over_a_loss() {
+        // C lets red fill D for four, while D leaves red only C, filling
+        // the board for a tie
+        let position = crate::Board::from_notation("YRYR/YRYY/YYR/RRR/d").unwrap();
+        let game = Game::<4, 4>::builder()
+            .first_player(Colour::Yellow)
+            .position(position)
+            .build();
+        assert_eq!(game.current_colour(), Colour::Yellow);
+
+        let mut bot = rubot::Bot::new(Colour::Yellow);
+        assert_eq!(bot.select(&game, rubot::Depth(4)), Column::new(3));
+
+        let tie = game.with_move(Column::new(3).unwrap()).unwrap();
+        let tie = tie.with_move(Column::new(2).unwrap()).unwrap();
+        let loss = game.with_move(Column::new(2).unwrap()).unwrap();
+        let loss = loss.with_move(Column::new(3).unwrap()).unwrap();
+        assert!(tie.is_tie());
+        assert_eq!(loss.loser(), Some(Colour::Yellow));
+        assert_eq!(tie.evaluate(Colour::Yellow), Fitness::TIE);
+        assert!(loss.evaluate(Colour::Yellow) < Fitness::TIE);
+        assert!(loss.evaluate(Colour::Red) > Fitness::TIE);
+    }
+}
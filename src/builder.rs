@@ -0,0 +1,207 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use bitvec::prelude::*;
+
+use crate::{
+    BlockError, Board, Colour, Column, FlipPolicy, Game, Gravity, Winner, MAX_CELLS, WIN_LENGTH,
+};
+
+/// Settings for a new game, defaulting to the classic rules with red going
+/// first and no flipping.
+#[derive(Debug, Clone)]
+pub struct GameBuilder<const W: usize, const H: usize> {
+    first_player: Colour,
+    flipping: bool,
+    flip_interval: u8,
+    flip_policy: FlipPolicy,
+    win_length: usize,
+    gravity: Gravity,
+    swap_rule: bool,
+    pop_out: bool,
+    persist_wins_through_flip: bool,
+    blocked: bitarr![for MAX_CELLS],
+    position: Option<Board<W, H>>,
+}
+
+impl<const W: usize, const H: usize> Default for GameBuilder<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize, const H: usize> GameBuilder<W, H> {
+    pub fn new() -> Self {
+        Self {
+            first_player: Colour::Red,
+            flipping: false,
+            flip_interval: 2,
+            flip_policy: FlipPolicy::Alternate,
+            win_length: WIN_LENGTH,
+            gravity: Gravity::Down,
+            swap_rule: false,
+            pop_out: false,
+            persist_wins_through_flip: true,
+            blocked: Default::default(),
+            position: None,
+        }
+    }
+
+    pub fn first_player(mut self, colour: Colour) -> Self {
+        self.first_player = colour;
+        self
+    }
+
+    pub fn flipping(mut self, flipping: bool) -> Self {
+        self.flipping = flipping;
+        self
+    }
+
+    /// How many moves are made between each flip when flipping, zero
+    /// meaning never.
+    pub fn flip_interval(mut self, moves: u8) -> Self {
+        self.flip_interval = moves;
+        self
+    }
+
+    /// Which way gravity points after each flip, alternating by default.
+    pub fn flip_policy(mut self, policy: FlipPolicy) -> Self {
+        self.flip_policy = policy;
+        self
+    }
+
+    /// How many discs in a row win, which has to fit on the board.
+    ///
+    /// # Panics
+    ///
+    /// If `length` is zero, as every board would already be won, or longer
+    /// than the board is wide and tall, as it could never be won.
+    pub fn win_length(mut self, length: usize) -> Self {
+        assert!(
+            (1..=W.max(H)).contains(&length),
+            "a line of {} can't be made on a {}x{} board",
+            length,
+            W,
+            H
+        );
+        self.win_length = length;
+        self
+    }
+
+    /// The wall discs settle against at the start of the game.
+    pub fn gravity(mut self, gravity: Gravity) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Lets the second player swap sides instead of answering the first
+    /// move, with `Move::Swap`.
+    pub fn swap_rule(mut self, swap_rule: bool) -> Self {
+        self.swap_rule = swap_rule;
+        self
+    }
+
+    /// Lets players take one of their own discs out from the wall of a
+    /// column with `Move::Pop`, as in Pop Out. Off by default.
+    pub fn pop_out(mut self, pop_out: bool) -> Self {
+        self.pop_out = pop_out;
+        self
+    }
+
+    /// Whether a line made by the last move of a round wins straight away,
+    /// which is the default. Otherwise the round's flip happens first and
+    /// only lines left after it count, so a flip can break up a line that
+    /// would have won.
+    pub fn persist_wins_through_flip(mut self, persist: bool) -> Self {
+        self.persist_wins_through_flip = persist;
+        self
+    }
+
+    /// Stops discs ever going in a cell, so it's never part of a line. Discs
+    /// settle past blocked cells as if they weren't there.
+    ///
+    /// Fails for a cell off the board, or holding a disc in the `position`.
+    pub fn block(mut self, column: Column<W>, row: u8) -> Result<Self, BlockError<W>> {
+        if row as usize >= H {
+            return Err(BlockError::OffBoard(column, row));
+        }
+        if let Some(board) = &self.position {
+            if board.piece_at(column, row).is_some() {
+                return Err(BlockError::Occupied(column, row));
+            }
+        }
+
+        self.blocked.set(Board::<W, H>::index_of(column, row), true);
+        Ok(self)
+    }
+
+    /// Caps how many discs each column holds, from the bottom, by blocking
+    /// the cells above its height. Fails like `block`.
+    pub fn column_heights(mut self, heights: [u8; W]) -> Result<Self, BlockError<W>> {
+        for (column, height) in Column::all().zip(heights) {
+            for row in height..H as u8 {
+                self = self.block(column, row)?;
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Starts from discs already on the board, eg. one made with
+    /// `Board::from_grid`, with `first_player` to move. Its gravity replaces
+    /// the one set with `gravity`, and its blocked cells replace any blocked
+    /// so far, so cells to block on top of those are blocked after it.
+    ///
+    /// The position counts as the start of a round, so the first flip comes
+    /// `flip_interval` moves into the game, and none of the discs already
+    /// placed can be undone. Saves only hold the moves made, so they don't
+    /// keep the position either.
+    pub fn position(mut self, board: Board<W, H>) -> Self {
+        self.blocked = Default::default();
+        self.position = Some(board);
+        self
+    }
+
+    pub fn build(self) -> Game<W, H> {
+        let mut game = Game {
+            state: Board::with_gravity(self.gravity),
+            first_player: self.first_player,
+            current_colour: self.first_player,
+            winner: None,
+            flipping: self.flipping,
+            flip_interval: self.flip_interval,
+            flip_policy: self.flip_policy,
+            round: 0,
+            intervals: 0,
+            start_gravity: self.gravity,
+            tie_break: 0,
+            swap_rule: self.swap_rule,
+            pop_out: self.pop_out,
+            swapped: false,
+            persist_wins_through_flip: self.persist_wins_through_flip,
+            ply: 0,
+            #[cfg(feature = "alloc")]
+            history: Vec::new(),
+            win_length: self.win_length,
+        };
+
+        if let Some(board) = self.position {
+            game.state = board;
+            game.start_gravity = game.state.gravity();
+        }
+
+        for idx in self.blocked.iter_ones() {
+            game.state
+                .block(Column(idx / H), (idx % H) as u8)
+                .expect("blocked cells are checked against the position");
+        }
+
+        // the position might already be over, or every cell blocked
+        game.winner = game.check_win_all();
+        if game.winner.is_none() && game.state.is_full() {
+            game.winner = Some(Winner::Tie);
+        }
+
+        game
+    }
+}
@@ -0,0 +1,168 @@
+//! A compact binary form of a `Board`, for storing lots of positions.
+//!
+//! Each cell takes two bits, going up each column in turn from A, so cell
+//! `(column, row)` is `column * H + row`. The first bit is set when the cell
+//! holds a disc and the second when that disc is red. A cell without a disc
+//! but with the second bit set is blocked. The two bits after the cells hold
+//! the gravity: `0` down, `1` up, `2` left and `3` right.
+//!
+//! Bits fill each byte from its least significant end, and whatever is left
+//! of the last byte is zero, so a classic board takes 11 bytes.
+
+use alloc::{vec, vec::Vec};
+
+use crate::{Board, Colour, Column, Gravity};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BytesError<const W: usize> {
+    /// How many bytes were given, which wasn't `Board::BYTES`.
+    WrongLength(usize),
+    /// The first disc, going up each column in turn, that gravity would move.
+    Floating(Column<W>, u8),
+}
+
+impl<const W: usize> core::fmt::Display for BytesError<W> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BytesError::WrongLength(n) => write!(f, "found {} bytes", n),
+            BytesError::Floating(column, row) => {
+                write!(
+                    f,
+                    "the disc in column {} row {} isn't supported",
+                    column, row
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const W: usize> std::error::Error for BytesError<W> {}
+
+fn bit(bytes: &[u8], idx: usize) -> bool {
+    bytes[idx / 8] >> (idx % 8) & 1 == 1
+}
+
+fn set_bit(bytes: &mut [u8], idx: usize, value: bool) {
+    bytes[idx / 8] |= (value as u8) << (idx % 8);
+}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// How many bytes `to_bytes` gives.
+    pub const BYTES: usize = (2 * W * H + 2).div_ceil(8);
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; Self::BYTES];
+
+        for (column, row, disc) in self.iter_cells() {
+            let idx = Self::index_of(column, row);
+            let second = match disc {
+                Some(colour) => colour == Colour::Red,
+                None => self.is_blocked(column, row),
+            };
+
+            set_bit(&mut bytes, 2 * idx, disc.is_some());
+            set_bit(&mut bytes, 2 * idx + 1, second);
+        }
+
+        let gravity = match self.gravity {
+            Gravity::Down => 0,
+            Gravity::Up => 1,
+            Gravity::Left => 2,
+            Gravity::Right => 3,
+        };
+        set_bit(&mut bytes, 2 * W * H, gravity & 1 == 1);
+        set_bit(&mut bytes, 2 * W * H + 1, gravity & 2 == 2);
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BytesError<W>> {
+        if bytes.len() != Self::BYTES {
+            return Err(BytesError::WrongLength(bytes.len()));
+        }
+
+        let gravity = match (bit(bytes, 2 * W * H), bit(bytes, 2 * W * H + 1)) {
+            (false, false) => Gravity::Down,
+            (true, false) => Gravity::Up,
+            (false, true) => Gravity::Left,
+            (true, true) => Gravity::Right,
+        };
+
+        let mut board = Board::with_gravity(gravity);
+
+        // the blocked cells all go in before any disc, which would otherwise
+        // be checked against lanes that don't skip them yet
+        let cells =
+            || Column::<W>::all().flat_map(|column| (0..H as u8).map(move |row| (column, row)));
+        for (column, row) in cells() {
+            let idx = Self::index_of(column, row);
+            if !bit(bytes, 2 * idx) && bit(bytes, 2 * idx + 1) {
+                board.block(column, row).unwrap();
+            }
+        }
+
+        for (column, row) in cells() {
+            let idx = Self::index_of(column, row);
+            if bit(bytes, 2 * idx) {
+                let disc = Colour::from_bool(bit(bytes, 2 * idx + 1));
+                board.set_cell(column, row, Some(disc));
+            }
+        }
+
+        match board.floating_disc() {
+            Some((column, row)) => Err(BytesError::Floating(column, row)),
+            None => Ok(board),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{Classic, ClassicBoard};
+
+    #[test]
+    fn classic_boards_take_eleven_bytes() {
+        assert_eq!(ClassicBoard::BYTES, 11);
+        assert_eq!(ClassicBoard::new().to_bytes().len(), 11);
+    }
+
+    #[test]
+    fn boards_come_back_from_their_bytes() {
+        let mut game = Classic::new(Colour::Red, true);
+        for &column in &[
+            Column::D,
+            Column::D,
+            Column::C,
+            Column::E,
+            Column::G,
+            Column::A,
+        ] {
+            game.make_move(column).unwrap();
+
+            let board = game.state();
+            assert_eq!(
+                ClassicBoard::from_bytes(&board.to_bytes()),
+                Ok(board.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn bad_bytes_are_rejected() {
+        assert_eq!(
+            ClassicBoard::from_bytes(&[0; 10]),
+            Err(BytesError::WrongLength(10))
+        );
+
+        // a red disc in A2 with nothing under it
+        let mut bytes = [0; 11];
+        set_bit(&mut bytes, 4, true);
+        set_bit(&mut bytes, 5, true);
+        assert_eq!(
+            ClassicBoard::from_bytes(&bytes),
+            Err(BytesError::Floating(Column::A, 2))
+        );
+    }
+}
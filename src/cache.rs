@@ -0,0 +1,241 @@
+//! Search results kept between games.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use crate::book::mirror_column;
+use crate::{Colour, Column, FlipPolicy, Game};
+
+/// Moves found by earlier searches, shared by every bot given a clone of it
+/// so that positions reached again, in the same game or another, aren't
+/// searched from scratch. Like the opening book, positions are keyed by
+/// `Board::canonical_key` so a position and its mirror share an entry.
+///
+/// At most `capacity` positions are kept, the least recently used being
+/// forgotten to make room.
+#[derive(Debug, Clone)]
+pub struct AnalysisCache<const W: usize> {
+    inner: Arc<Mutex<Entries<W>>>,
+}
+
+#[derive(Debug)]
+struct Entries<const W: usize> {
+    entries: HashMap<Key, Entry<W>>,
+    /// The key of every entry by when it was last used, oldest first.
+    by_use: BTreeMap<u64, Key>,
+    capacity: usize,
+    // bumped on every insert and hit, so the entry with the lowest `used` is
+    // the least recently used
+    clock: u64,
+    hits: u64,
+}
+
+/// Everything besides the board that changes which move is best.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    board: u64,
+    to_move: Colour,
+    round: u8,
+    flip_interval: u8,
+    flip_policy: FlipPolicy,
+    /// Only counted for random flips, for which it decides the flips to come.
+    intervals: u32,
+    win_length: usize,
+    persist_wins_through_flip: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry<const W: usize> {
+    column: Column<W>,
+    depth: u32,
+    used: u64,
+}
+
+impl<const W: usize> Entries<W> {
+    /// Moves `key` from when it was `last_used` to `used`.
+    fn touch(&mut self, key: Key, last_used: u64, used: u64) {
+        self.by_use.remove(&last_used);
+        self.by_use.insert(used, key);
+    }
+}
+
+impl<const W: usize> AnalysisCache<W> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Entries {
+                entries: HashMap::new(),
+                by_use: BTreeMap::new(),
+                capacity,
+                clock: 0,
+                hits: 0,
+            })),
+        }
+    }
+
+    /// The move found for `game` and how deeply it was searched, if it was
+    /// searched at least `depth` deep.
+    pub fn lookup<const H: usize>(
+        &self,
+        game: &Game<W, H>,
+        depth: u32,
+    ) -> Option<(Column<W>, u32)> {
+        let (key, mirrored) = Self::key(game);
+        let mut inner = self.inner.lock().unwrap();
+
+        inner.clock += 1;
+        let clock = inner.clock;
+
+        let entry = inner.entries.get_mut(&key).filter(|e| e.depth >= depth)?;
+        let last_used = std::mem::replace(&mut entry.used, clock);
+        let (column, depth) = (entry.column, entry.depth);
+
+        inner.touch(key, last_used, clock);
+        inner.hits += 1;
+
+        let column = if mirrored {
+            mirror_column(column)
+        } else {
+            column
+        };
+
+        Some((column, depth))
+    }
+
+    /// Remembers `column` as the move for `game`, found by a search `depth`
+    /// deep, unless a deeper search of it is already remembered.
+    pub fn insert<const H: usize>(&self, game: &Game<W, H>, column: Column<W>, depth: u32) {
+        let (key, mirrored) = Self::key(game);
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.capacity == 0 {
+            return;
+        }
+
+        inner.clock += 1;
+        let used = inner.clock;
+
+        if let Some(entry) = inner.entries.get_mut(&key) {
+            let last_used = std::mem::replace(&mut entry.used, used);
+            let deeper = entry.depth >= depth;
+
+            inner.touch(key, last_used, used);
+            if deeper {
+                return;
+            }
+        } else {
+            if inner.entries.len() >= inner.capacity {
+                if let Some((_, oldest)) = inner.by_use.pop_first() {
+                    inner.entries.remove(&oldest);
+                }
+            }
+
+            inner.by_use.insert(used, key);
+        }
+
+        // moves are stored as they'd be played on whichever of the board and
+        // its mirror gives the key
+        let column = if mirrored {
+            mirror_column(column)
+        } else {
+            column
+        };
+
+        inner.entries.insert(
+            key,
+            Entry {
+                column,
+                depth,
+                used,
+            },
+        );
+    }
+
+    /// How many lookups have found a move.
+    pub fn hits(&self) -> u64 {
+        self.inner.lock().unwrap().hits
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn key<const H: usize>(game: &Game<W, H>) -> (Key, bool) {
+        let board = game.state.canonical_key();
+
+        let key = Key {
+            board,
+            to_move: game.current_colour,
+            round: game.round,
+            flip_interval: if game.flips() { game.flip_interval } else { 0 },
+            flip_policy: game.flip_policy,
+            intervals: match game.flip_policy {
+                FlipPolicy::Random(_) => game.intervals,
+                _ => 0,
+            },
+            win_length: game.win_length,
+            persist_wins_through_flip: game.persist_wins_through_flip,
+        };
+
+        (key, game.state.key() != board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{Bot, Classic, Difficulty};
+
+    /// Plays a game between two seeded bots sharing `cache`.
+    fn play(cache: &AnalysisCache<7>) {
+        let mut game = Classic::new(Colour::Red, false);
+        let mut bots = [Colour::Red, Colour::Yellow].map(|colour| {
+            let mut bot = Bot::with_seed(colour, Duration::from_secs(1), 64);
+            bot.use_cache(cache.clone());
+            bot
+        });
+
+        while !game.is_finished() {
+            let bot = &mut bots[(game.current_colour() == Colour::Yellow) as usize];
+            let column = bot.select_move(&game, Difficulty::Easy).unwrap();
+            game.make_move(column).unwrap();
+        }
+    }
+
+    #[test]
+    fn a_second_game_hits_the_first_games_searches() {
+        let cache = AnalysisCache::new(1000);
+
+        play(&cache);
+        let hits = cache.hits();
+        play(&cache);
+
+        assert!(cache.hits() > hits);
+    }
+
+    #[test]
+    fn the_least_recently_used_position_is_forgotten() {
+        let cache = AnalysisCache::new(2);
+        let games = ["A", "B", "C"].map(|column| {
+            let mut game = Classic::new(Colour::Red, false);
+            game.make_move(column.parse().unwrap()).unwrap();
+            game
+        });
+
+        cache.insert(&games[0], Column::D, 4);
+        cache.insert(&games[1], Column::D, 4);
+        // using the first makes the second the oldest
+        assert!(cache.lookup(&games[0], 4).is_some());
+        cache.insert(&games[2], Column::D, 4);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.lookup(&games[0], 4).is_some());
+        assert!(cache.lookup(&games[1], 4).is_none());
+        assert!(cache.lookup(&games[2], 4).is_some());
+    }
+}
@@ -0,0 +1,163 @@
+//! Positions from played games as training records.
+
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::{Colour, Column, Game, MoveError};
+
+/// One position of a game, with the move played from it and how the game
+/// ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<const W: usize> {
+    /// Every cell in `Board::iter_cells` order: `1` for red, `-1` for yellow
+    /// and `0` when empty.
+    pub cells: Vec<i8>,
+    pub to_move: Colour,
+    /// The winner of the game, `None` if it was tied.
+    pub result: Option<Colour>,
+    /// `None` for the final position.
+    pub chosen: Option<Column<W>>,
+}
+
+impl<const W: usize> Record<W> {
+    /// Replays `moves`, giving a record for the starting position and the
+    /// position after every move.
+    pub fn from_replay<const H: usize>(
+        moves: &[Column<W>],
+        starting_colour: Colour,
+        flipping: bool,
+    ) -> Result<Vec<Self>, (usize, MoveError<W>)> {
+        let start = Game::<W, H>::new(starting_colour, flipping);
+        let games = core::iter::once(Ok(start))
+            .chain(Game::replay_iter(moves, starting_colour, flipping))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let result = games
+            .last()
+            .and_then(|game| game.winner())
+            .and_then(|winner| winner.to_colour());
+
+        let records = games
+            .iter()
+            .enumerate()
+            .map(|(idx, game)| Record {
+                cells: game
+                    .state()
+                    .iter_cells()
+                    .map(|(_, _, disc)| match disc {
+                        Some(Colour::Red) => 1,
+                        Some(Colour::Yellow) => -1,
+                        None => 0,
+                    })
+                    .collect(),
+                to_move: game.current_colour(),
+                result,
+                chosen: moves.get(idx).copied(),
+            })
+            .collect();
+
+        Ok(records)
+    }
+
+    /// The record as a line of JSON, eg.
+    /// `{"cells":[0,1,...],"to_move":"Y","result":"R","move":"D"}`.
+    pub fn to_json(&self) -> String {
+        let cells = self
+            .cells
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let result = self
+            .result
+            .map_or("null".to_owned(), |c| format!("\"{}\"", c));
+        let chosen = self
+            .chosen
+            .map_or("null".to_owned(), |c| format!("\"{}\"", c));
+
+        format!(
+            "{{\"cells\":[{}],\"to_move\":\"{}\",\"result\":{},\"move\":{}}}",
+            cells, self.to_move, result, chosen
+        )
+    }
+
+    /// The header line matching `to_csv`.
+    pub fn csv_header(&self) -> String {
+        let cells = (0..self.cells.len()).map(|idx| format!("c{}", idx));
+
+        cells
+            .chain(["to_move", "result", "move"].iter().map(|&s| s.to_owned()))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// The record as a line of CSV: one field per cell, then the side to
+    /// move, the result and the move, with the last two empty when there
+    /// isn't one.
+    pub fn to_csv(&self) -> String {
+        let result = self.result.map_or(String::new(), |c| c.to_string());
+        let chosen = self.chosen.map_or(String::new(), |c| c.to_string());
+
+        self.cells
+            .iter()
+            .map(|c| c.to_string())
+            .chain(vec![self.to_move.to_string(), result, chosen])
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_give_a_record_per_position() {
+        let moves = [
+            Column::D,
+            Column::D,
+            Column::C,
+            Column::C,
+            Column::B,
+            Column::B,
+            Column::A,
+        ];
+        let records = Record::<7>::from_replay::<6>(&moves, Colour::Red, false).unwrap();
+
+        assert_eq!(records.len(), moves.len() + 1);
+        assert!(records.iter().all(|r| r.result == Some(Colour::Red)));
+        assert_eq!(records[0].cells, vec![0; 42]);
+        assert_eq!(records[0].chosen, Some(Column::D));
+        assert_eq!(records[1].to_move, Colour::Yellow);
+        assert_eq!(records[7].chosen, None);
+    }
+
+    #[test]
+    fn replays_with_an_illegal_move_give_its_index() {
+        let moves = [Column::A; 7];
+        let err = Record::<7>::from_replay::<6>(&moves, Colour::Red, false).unwrap_err();
+        assert_eq!(err, (6, MoveError::ColumnFull(Column::A)));
+    }
+
+    #[test]
+    fn records_are_written_as_json_and_csv() {
+        let record = Record::<2> {
+            cells: vec![1, 0, -1, 0],
+            to_move: Colour::Red,
+            result: None,
+            chosen: Some(Column::new(1).unwrap()),
+        };
+
+        assert_eq!(
+            record.to_json(),
+            r#"{"cells":[1,0,-1,0],"to_move":"R","result":null,"move":"B"}"#
+        );
+        assert_eq!(record.csv_header(), "c0,c1,c2,c3,to_move,result,move");
+        assert_eq!(record.to_csv(), "1,0,-1,0,R,,B");
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::{Colour, Game, Winner};
+
+/// The game tree `depth` moves deep from `game` as a graphviz DOT graph.
+/// Positions are labelled with their notation, and finished ones with who
+/// won. With `merge` set, positions that are the same up to mirroring are
+/// drawn as one node.
+pub fn game_tree_dot<const W: usize, const H: usize>(
+    game: &Game<W, H>,
+    depth: usize,
+    merge: bool,
+) -> String {
+    let mut tree = Tree {
+        out: String::from("digraph {\n"),
+        seen: HashMap::new(),
+        nodes: 0,
+        merge,
+    };
+
+    tree.node(game, depth);
+    tree.out.push_str("}\n");
+
+    tree.out
+}
+
+struct Tree {
+    out: String,
+    seen: HashMap<(u64, Colour), usize>,
+    nodes: usize,
+    merge: bool,
+}
+
+impl Tree {
+    /// Writes the node for `game` and everything below it, giving its id.
+    fn node<const W: usize, const H: usize>(&mut self, game: &Game<W, H>, depth: usize) -> usize {
+        let key = (game.state().canonical_key(), game.current_colour());
+
+        if self.merge {
+            if let Some(&id) = self.seen.get(&key) {
+                return id;
+            }
+        }
+
+        let id = self.nodes;
+        self.nodes += 1;
+        self.seen.insert(key, id);
+
+        let label = match game.winner() {
+            Some(Winner::Tie) => format!("{}\\ntie", game.state().to_notation()),
+            Some(winner) => format!("{}\\n{}", game.state().to_notation(), winner),
+            None => game.state().to_notation(),
+        };
+        writeln!(self.out, "    {} [label=\"{}\"];", id, label).unwrap();
+
+        if depth == 0 {
+            return id;
+        }
+
+        for column in game.legal_moves() {
+            let child = self.node(&game.with_move(column).unwrap(), depth - 1);
+            writeln!(self.out, "    {} -> {} [label=\"{}\"];", id, child, column).unwrap();
+        }
+
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Classic, Column};
+
+    #[test]
+    fn one_move_deep_has_an_edge_per_column() {
+        let dot = game_tree_dot(&Classic::new(Colour::Red, false), 1, false);
+
+        assert_eq!(dot.matches(" -> ").count(), 7);
+        assert_eq!(dot.matches("[label=").count(), 8 + 7);
+    }
+
+    #[test]
+    fn merged_trees_draw_mirrored_positions_once() {
+        let game = Classic::new(Colour::Red, false);
+
+        let dot = game_tree_dot(&game, 2, false);
+        assert_eq!(dot.matches(" -> ").count(), 7 + 49);
+        assert_eq!(dot.matches("\\n").count(), 0);
+
+        // every edge is still drawn, into fewer positions
+        let merged = game_tree_dot(&game, 2, true);
+        let nodes = merged.lines().filter(|l| !l.contains("->")).count() - 2;
+        assert_eq!(merged.matches(" -> ").count(), 7 + 4 * 7);
+        assert!(nodes < 1 + 7 + 49, "{}", merged);
+    }
+
+    #[test]
+    fn finished_positions_say_who_won() {
+        let mut game = Classic::new(Colour::Red, false);
+        for &column in &[
+            Column::A,
+            Column::B,
+            Column::A,
+            Column::B,
+            Column::A,
+            Column::B,
+        ] {
+            game.make_move(column).unwrap();
+        }
+
+        let dot = game_tree_dot(&game, 1, false);
+        assert!(dot.contains("RRRR/YYY/-/-/-/-/-/d\\nRed wins"), "{}", dot);
+    }
+}
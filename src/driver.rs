@@ -0,0 +1,476 @@
+use std::time::{Duration, Instant};
+
+use crate::colour_name;
+use crate::{Bot, Colour, Column, Difficulty, Game, Move, MoveError, SearchStats};
+
+/// How a bot plays its colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BotConfig {
+    pub difficulty: Difficulty,
+    pub think_time: Duration,
+    /// Makes the bot play the same way every time, see `Bot::with_seed`.
+    pub seed: Option<u64>,
+    /// Whether to search for answers while the opponent thinks, see
+    /// `Bot::ponder`.
+    pub ponder: bool,
+}
+
+impl BotConfig {
+    pub fn new(difficulty: Difficulty, think_time: Duration) -> Self {
+        Self {
+            difficulty,
+            think_time,
+            seed: None,
+            ponder: false,
+        }
+    }
+
+    fn bot<const W: usize, const H: usize>(&self, colour: Colour) -> Bot<W, H> {
+        match self.seed {
+            Some(seed) => Bot::with_seed(colour, self.think_time, seed),
+            None => Bot::new(colour, self.think_time),
+        }
+    }
+}
+
+/// Who plays a colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerKind {
+    /// Someone asked for each move.
+    Human,
+    Bot(BotConfig),
+}
+
+/// How much time each side has to think, a side running out losing the
+/// game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeControl {
+    /// The same time for every move, none of it carried over.
+    PerMove(Duration),
+    /// One bank of time for the whole game.
+    SuddenDeath(Duration),
+    /// A bank of time, with `increment` added to it after every move.
+    Fischer { base: Duration, increment: Duration },
+}
+
+// sudden death bots spend their bank as if this many moves were left, which
+// is about how many each side makes in a classic game
+const MOVES_LEFT: u32 = 20;
+
+impl TimeControl {
+    /// How much time each side starts with.
+    fn bank(self) -> Duration {
+        match self {
+            TimeControl::PerMove(limit) => limit,
+            TimeControl::SuddenDeath(base) | TimeControl::Fischer { base, .. } => base,
+        }
+    }
+
+    /// How long a bot with `remaining` on its clock should search for,
+    /// leaving a margin for the search running over.
+    pub fn think_time(self, remaining: Duration) -> Duration {
+        match self {
+            TimeControl::PerMove(limit) => limit * 3 / 4,
+            TimeControl::SuddenDeath(_) => remaining / MOVES_LEFT,
+            TimeControl::Fischer { increment, .. } => {
+                (remaining / MOVES_LEFT + increment * 3 / 4).min(remaining * 3 / 4)
+            }
+        }
+    }
+
+    /// The clock after a move that took `elapsed` with `remaining` on it,
+    /// `None` if it ran out.
+    fn charge(self, remaining: Duration, elapsed: Duration) -> Option<Duration> {
+        let left = remaining.checked_sub(elapsed)?;
+
+        Some(match self {
+            TimeControl::PerMove(limit) => limit,
+            TimeControl::SuddenDeath(_) => left,
+            TimeControl::Fischer { increment, .. } => left + increment,
+        })
+    }
+}
+
+/// What a bot did with its turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotTurn<const W: usize> {
+    /// Took the first move as its own under the swap rule.
+    Swapped,
+    Played(SearchStats<W>),
+    /// Ran out of time searching, losing the game.
+    OutOfTime,
+    /// The search gave up without a move, leaving the game unfinished.
+    NoMove,
+}
+
+/// A bot chose a move the game refused, which is a bug in the bot. The game
+/// is left as it was before the move.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IllegalBotMove<const W: usize> {
+    /// The colour the bot was playing.
+    pub colour: Colour,
+    pub mv: Move<W>,
+    pub error: MoveError<W>,
+}
+
+impl<const W: usize> std::fmt::Display for IllegalBotMove<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mv = match self.mv {
+            Move::Drop(column) => column.to_string(),
+            Move::Pop(column) => format!("pop {}", column),
+            Move::Swap => "swap".to_owned(),
+        };
+
+        write!(
+            f,
+            "the {} bot chose the illegal move {}: {}",
+            colour_name(self.colour).to_ascii_lowercase(),
+            mv,
+            self.error
+        )
+    }
+}
+
+impl<const W: usize> std::error::Error for IllegalBotMove<W> {}
+
+/// A game along with who plays each colour, so that a front end only has to
+/// ask humans for their moves and can leave the bots to the driver.
+pub struct Driver<const W: usize, const H: usize> {
+    game: Game<W, H>,
+    /// Who plays red then yellow.
+    players: [PlayerKind; 2],
+    bots: [Option<Bot<W, H>>; 2],
+    time_control: Option<TimeControl>,
+    /// The time red then yellow have left, under a time control.
+    clocks: [Duration; 2],
+    /// When the current player's turn began.
+    turn_start: Instant,
+    /// The colour that lost by running out of time.
+    flagged: Option<Colour>,
+}
+
+fn index(colour: Colour) -> usize {
+    match colour {
+        Colour::Red => 0,
+        Colour::Yellow => 1,
+    }
+}
+
+impl<const W: usize, const H: usize> Driver<W, H> {
+    pub fn new(game: Game<W, H>, red: PlayerKind, yellow: PlayerKind) -> Self {
+        let mut driver = Self {
+            game,
+            players: [red, yellow],
+            bots: [None, None],
+            time_control: None,
+            clocks: [Duration::ZERO; 2],
+            turn_start: Instant::now(),
+            flagged: None,
+        };
+        driver.start_bots();
+
+        driver
+    }
+
+    /// Times both sides, humans included, instead of letting each bot search
+    /// for its `think_time`. The clocks start now.
+    pub fn with_time_control(mut self, time_control: TimeControl) -> Self {
+        self.time_control = Some(time_control);
+        self.clocks = [time_control.bank(); 2];
+        self.turn_start = Instant::now();
+        self
+    }
+
+    /// How long `colour` has left under the time control, if there is one,
+    /// not counting the turn in progress.
+    pub fn clock(&self, colour: Colour) -> Option<Duration> {
+        self.time_control.map(|_| self.clocks[index(colour)])
+    }
+
+    /// The colour that lost by running out of time, if one did.
+    pub fn flagged(&self) -> Option<Colour> {
+        self.flagged
+    }
+
+    /// Takes the time the current player's turn took off their clock,
+    /// ending the game if it ran out. Gives whether they were still in time.
+    fn stop_clock(&mut self) -> bool {
+        let elapsed = self.turn_start.elapsed();
+        self.turn_start = Instant::now();
+
+        let time_control = match self.time_control {
+            Some(time_control) => time_control,
+            None => return true,
+        };
+
+        let colour = self.game.current_colour();
+        match time_control.charge(self.clocks[index(colour)], elapsed) {
+            Some(left) => {
+                self.clocks[index(colour)] = left;
+                true
+            }
+            None => {
+                self.clocks[index(colour)] = Duration::ZERO;
+                self.flagged = Some(colour);
+                self.game.resign(colour).unwrap();
+                false
+            }
+        }
+    }
+
+    fn start_bots(&mut self) {
+        for &colour in Colour::all() {
+            self.bots[index(colour)] = match self.player(colour) {
+                PlayerKind::Human => None,
+                PlayerKind::Bot(config) => Some(config.bot(colour)),
+            };
+        }
+    }
+
+    pub fn game(&self) -> &Game<W, H> {
+        &self.game
+    }
+
+    pub fn into_game(self) -> Game<W, H> {
+        self.game
+    }
+
+    /// Who plays `colour`, which changes if sides are swapped.
+    pub fn player(&self, colour: Colour) -> PlayerKind {
+        self.players[index(colour)]
+    }
+
+    /// Whether the game is waiting on a human, rather than on `play_bot` or
+    /// being over.
+    pub fn is_human_turn(&self) -> bool {
+        !self.game.is_finished() && self.player(self.game.current_colour()) == PlayerKind::Human
+    }
+
+    /// Plays a move for the human whose turn it is. If they took too long
+    /// the move isn't made, and the game is lost on time.
+    pub fn play_human(&mut self, column: Column<W>) -> Result<(), MoveError<W>> {
+        self.game.validate_move(column)?;
+
+        if !self.stop_clock() {
+            return Err(MoveError::OutOfTime);
+        }
+
+        self.game.make_move(column)
+    }
+
+    /// Gives the game to the human's opponent.
+    pub fn resign(&mut self) -> Result<(), MoveError<W>> {
+        self.game.resign(self.game.current_colour())
+    }
+
+    /// Makes a `Move::Swap`, after which each side plays the other colour.
+    /// The time it took comes off the clock of the colour to move, which
+    /// goes on to be the other side's.
+    pub fn swap_sides(&mut self) -> Result<(), MoveError<W>> {
+        if !self.game.can_swap() {
+            return Err(MoveError::CannotSwap);
+        }
+
+        if !self.stop_clock() {
+            return Err(MoveError::OutOfTime);
+        }
+
+        self.game.make_move_ex(Move::Swap)?;
+
+        // bots play a single colour, so they start over on their new one
+        self.players.swap(0, 1);
+        self.start_bots();
+
+        Ok(())
+    }
+
+    /// Lets the bot whose turn it is move, giving `None` if it's a human's
+    /// turn or the game is over, or an error if the bot tried an illegal
+    /// move.
+    pub fn play_bot(&mut self) -> Result<Option<BotTurn<W>>, IllegalBotMove<W>> {
+        if self.game.is_finished() {
+            return Ok(None);
+        }
+
+        let colour = self.game.current_colour();
+        let config = match self.player(colour) {
+            PlayerKind::Human => return Ok(None),
+            PlayerKind::Bot(config) => config,
+        };
+
+        let think_time = match self.time_control {
+            Some(time_control) => time_control.think_time(self.clocks[index(colour)]),
+            None => config.think_time,
+        };
+
+        let bot = self.bots[index(colour)].as_mut().unwrap();
+        bot.set_think_time(think_time);
+
+        if bot.should_swap(&self.game) {
+            return match self.swap_sides() {
+                Ok(()) => Ok(Some(BotTurn::Swapped)),
+                Err(MoveError::OutOfTime) => Ok(Some(BotTurn::OutOfTime)),
+                Err(error) => Err(IllegalBotMove {
+                    colour,
+                    mv: Move::Swap,
+                    error,
+                }),
+            };
+        }
+
+        match bot.select_move_with_stats(&self.game, config.difficulty) {
+            Some(stats) => self.play_bot_move(stats).map(Some),
+            None => Ok(Some(BotTurn::NoMove)),
+        }
+    }
+
+    /// Makes the move the bot to move searched for, once its time is taken
+    /// off its clock.
+    fn play_bot_move(&mut self, stats: SearchStats<W>) -> Result<BotTurn<W>, IllegalBotMove<W>> {
+        let colour = self.game.current_colour();
+
+        // the search only offers legal moves, so this is a bug in the bot
+        if let Err(error) = self.game.validate_move(stats.chosen) {
+            return Err(IllegalBotMove {
+                colour,
+                mv: Move::Drop(stats.chosen),
+                error,
+            });
+        }
+
+        if !self.stop_clock() {
+            return Ok(BotTurn::OutOfTime);
+        }
+        self.game
+            .make_move(stats.chosen)
+            .expect("the move was validated");
+
+        if let PlayerKind::Bot(BotConfig { ponder: true, .. }) = self.player(colour) {
+            let bot = self.bots[index(colour)].as_mut().unwrap();
+            bot.ponder(&self.game);
+        }
+
+        Ok(BotTurn::Played(stats))
+    }
+
+    /// Plays bot moves until it's a human's turn, the game is over, or a bot
+    /// finds no move.
+    pub fn play_bots(&mut self) -> Result<(), IllegalBotMove<W>> {
+        while let Some(turn) = self.play_bot()? {
+            if turn == BotTurn::NoMove {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Classic, Winner};
+
+    fn bot(think_ms: u64) -> PlayerKind {
+        let mut config = BotConfig::new(Difficulty::Medium, Duration::from_millis(think_ms));
+        config.seed = Some(89);
+        PlayerKind::Bot(config)
+    }
+
+    #[test]
+    fn bots_play_a_whole_game_between_themselves() {
+        let easy = |seed| {
+            let mut config = BotConfig::new(Difficulty::Easy, Duration::from_millis(10));
+            config.seed = Some(seed);
+            PlayerKind::Bot(config)
+        };
+        let play = || {
+            let mut driver = Driver::new(Classic::new(Colour::Red, false), easy(81), easy(18));
+            driver.play_bots().unwrap();
+            driver.into_game()
+        };
+
+        let game = play();
+        assert!(game.is_finished());
+        assert!(!game.history().is_empty());
+
+        // the bots only ever made legal moves, which give the same result
+        // played again by hand
+        let mut replayed = Classic::new(Colour::Red, false);
+        for &mv in game.history() {
+            replayed.make_move_ex(mv).unwrap();
+        }
+        assert_eq!(replayed.winner(), game.winner());
+
+        // easy bots search to a fixed depth, so seeded ones play the same
+        // game every time
+        assert_eq!(play(), game);
+    }
+
+    #[test]
+    fn bots_with_a_tiny_bank_lose_on_time() {
+        // flipping games aren't in the opening book, so red has to search,
+        // which takes longer than its whole bank
+        let mut driver = Driver::new(Classic::new(Colour::Red, true), bot(10), bot(10))
+            .with_time_control(TimeControl::SuddenDeath(Duration::from_micros(1)));
+
+        assert_eq!(driver.play_bot(), Ok(Some(BotTurn::OutOfTime)));
+        assert_eq!(driver.play_bot(), Ok(None));
+
+        assert_eq!(driver.flagged(), Some(Colour::Red));
+        assert_eq!(driver.clock(Colour::Red), Some(Duration::ZERO));
+        assert_eq!(driver.game().winner(), Some(Winner::Yellow));
+        assert_eq!(driver.game().ply(), 0);
+    }
+
+    #[test]
+    fn illegal_bot_moves_are_errors() {
+        let mut game = Classic::new(Colour::Red, false);
+        for _ in 0..6 {
+            game.make_move(Column::A).unwrap();
+        }
+        let mut driver = Driver::new(game.clone(), bot(10), bot(10));
+
+        // as if red's search had chosen the full column
+        let stats = SearchStats {
+            chosen: Column::A,
+            nodes: 0,
+            depth: 0,
+            elapsed: Duration::ZERO,
+        };
+        let error = driver.play_bot_move(stats).unwrap_err();
+
+        assert_eq!(
+            error,
+            IllegalBotMove {
+                colour: Colour::Red,
+                mv: Move::Drop(Column::A),
+                error: MoveError::ColumnFull(Column::A),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "the red bot chose the illegal move A: column A is full"
+        );
+        assert_eq!(driver.game(), &game);
+
+        // the game carries on as if nothing happened
+        assert!(matches!(driver.play_bot(), Ok(Some(BotTurn::Played(_)))));
+        assert_eq!(driver.game().ply(), 7);
+    }
+
+    #[test]
+    fn humans_out_of_time_are_told_so() {
+        let mut driver = Driver::new(
+            Classic::new(Colour::Red, false),
+            PlayerKind::Human,
+            PlayerKind::Human,
+        )
+        .with_time_control(TimeControl::SuddenDeath(Duration::from_millis(1)));
+        std::thread::sleep(Duration::from_millis(5));
+
+        assert_eq!(driver.play_human(Column::D), Err(MoveError::OutOfTime));
+        assert_eq!(driver.game().winner(), Some(Winner::Yellow));
+        assert_eq!(driver.game().ply(), 0);
+    }
+}
@@ -0,0 +1,356 @@
+use crate::{row_offset, Board, Colour, Column, Game, Move, Winner, WIN_LENGTH};
+
+/// How good a position is for one colour, higher being better.
+///
+/// Finished games score past `±DECIDED`, with sooner wins (and later losses)
+/// further out so the bot takes the quickest win it can find. Unfinished
+/// positions get a heuristic score inside that range, with positions that
+/// score the same told apart by how central the last move was.
+///
+/// A tie scores zero for both colours, so it's worse than any win and better
+/// than any loss, and the side the heuristic has behind steers toward one
+/// while the side ahead steers away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fitness(i32);
+
+impl Fitness {
+    const WIN: i32 = 1_000_000;
+    const DECIDED: i32 = Self::WIN / 2;
+
+    pub const TIE: Fitness = Fitness(0);
+
+    pub(crate) fn win_at(ply: usize) -> Self {
+        Fitness(Self::WIN - ply as i32)
+    }
+
+    pub(crate) fn loss_at(ply: usize) -> Self {
+        Fitness(-Self::WIN + ply as i32)
+    }
+
+    // big boards have enough windows to push the score past `DECIDED`,
+    // where it would pass for a win or a loss
+    fn heuristic(score: i32) -> Self {
+        Fitness(score.clamp(-Self::DECIDED, Self::DECIDED))
+    }
+
+    pub fn value(self) -> i32 {
+        self.0
+    }
+
+    pub fn is_win(self) -> bool {
+        self.0 > Self::DECIDED
+    }
+
+    pub fn is_loss(self) -> bool {
+        self.0 < -Self::DECIDED
+    }
+}
+
+impl core::fmt::Display for Fitness {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_win() {
+            write!(f, "Win")
+        } else if self.is_loss() {
+            write!(f, "Loss")
+        } else {
+            write!(f, "{:+}", self.0)
+        }
+    }
+}
+
+/// The parts of a heuristic score, see `Game::explain_eval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalBreakdown {
+    /// The colour the scores are for.
+    pub colour: Colour,
+    /// Runs of cells holding a whole line.
+    pub lines: i32,
+    /// Open runs one disc short of a line.
+    pub threes: i32,
+    /// Open runs two discs short of a line.
+    pub twos: i32,
+    /// Discs in the centre column, or the two centre columns on even width
+    /// boards.
+    pub centre: i32,
+    /// Who the threats' rows favour, see `Game::parity_advantage`. This isn't
+    /// part of the score.
+    pub parity: Option<Colour>,
+}
+
+impl EvalBreakdown {
+    /// The score the parts add up to, before `evaluate` breaks ties.
+    pub fn score(&self) -> i32 {
+        self.lines + self.threes + self.twos + self.centre
+    }
+}
+
+impl core::fmt::Display for EvalBreakdown {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "For {}:", self.colour)?;
+        writeln!(f, "  lines       {:+}", self.lines)?;
+        writeln!(f, "  open threes {:+}", self.threes)?;
+        writeln!(f, "  twos        {:+}", self.twos)?;
+        writeln!(f, "  centre      {:+}", self.centre)?;
+        writeln!(f, "  total       {:+}", self.score())?;
+
+        match self.parity {
+            Some(colour) => write!(f, "  parity favours {}", colour),
+            None => write!(f, "  parity favours neither"),
+        }
+    }
+}
+
+// a window holding a whole line
+const LINE_WEIGHT: i32 = 1000;
+// a window one disc short of a line, with the last cell still empty
+const THREE_WEIGHT: i32 = 100;
+// a window two discs short of a line
+const TWO_WEIGHT: i32 = 10;
+// per disc in the centre column(s)
+const CENTRE_WEIGHT: i32 = 3;
+
+const DIRECTIONS: [(i8, i8); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// The weight of a run of `len` cells holding `discs` discs of one colour
+/// and none of the other.
+fn window_weight(discs: usize, len: usize) -> i32 {
+    if discs == 0 {
+        0
+    } else if discs == len {
+        LINE_WEIGHT
+    } else if discs + 1 == len {
+        THREE_WEIGHT
+    } else if discs + 2 == len {
+        TWO_WEIGHT
+    } else {
+        0
+    }
+}
+
+impl<const W: usize, const H: usize> Game<W, H> {
+    pub fn evaluate(&self, colour: Colour) -> Fitness {
+        let ply = self.ply();
+
+        match self.winner {
+            Some(Winner::Tie) => return Fitness::TIE,
+            Some(w) if w.to_colour() == Some(colour) => return Fitness::win_at(ply),
+            Some(_) => return Fitness::loss_at(ply),
+            None => (),
+        }
+
+        let score = self.score_parts(colour).score();
+
+        // scale the score up so the tie break only matters between equal
+        // scores
+        let tie_break = match self.history.last() {
+            Some(Move::Drop(c)) | Some(Move::Pop(c)) => {
+                let centrality = (W - 1) as i32 - (2 * c.to_idx() as i32 - (W - 1) as i32).abs();
+
+                if self.current_colour == colour {
+                    -centrality
+                } else {
+                    centrality
+                }
+            }
+            Some(Move::Swap) | None => 0,
+        };
+
+        Fitness::heuristic(score * 2 * W as i32 + tie_break)
+    }
+
+    /// The parts of the heuristic score `evaluate` gives `colour`, each of
+    /// them its own minus its opponent's, ignoring whether the game is over.
+    pub fn explain_eval(&self, colour: Colour) -> EvalBreakdown {
+        EvalBreakdown {
+            parity: self.parity_advantage(),
+            ..self.score_parts(colour)
+        }
+    }
+
+    /// The parts of the score without the parity, which is too slow to work
+    /// out at every node of a search.
+    fn score_parts(&self, colour: Colour) -> EvalBreakdown {
+        let len = self.win_length;
+        let mut breakdown = EvalBreakdown {
+            colour,
+            lines: 0,
+            threes: 0,
+            twos: 0,
+            centre: 0,
+            parity: None,
+        };
+
+        for (mine, theirs) in self.window_counts(colour) {
+            // windows holding both colours can never become a line
+            let (discs, sign) = match (mine, theirs) {
+                (m, 0) => (m, 1),
+                (0, t) => (t, -1),
+                _ => continue,
+            };
+
+            let weight = window_weight(discs, len);
+            match weight {
+                LINE_WEIGHT => breakdown.lines += sign * weight,
+                THREE_WEIGHT => breakdown.threes += sign * weight,
+                TWO_WEIGHT => breakdown.twos += sign * weight,
+                _ => (),
+            }
+        }
+
+        // one centre column on odd width boards, two on even ones
+        for idx in (W - 1) / 2..=W / 2 {
+            for row in 0..H as u8 {
+                match self.state.piece_at(Column(idx), row) {
+                    Some(p) if p == colour => breakdown.centre += CENTRE_WEIGHT,
+                    Some(_) => breakdown.centre -= CENTRE_WEIGHT,
+                    None => (),
+                }
+            }
+        }
+
+        breakdown
+    }
+
+    /// Whether neither colour can ever make a line, as every run of
+    /// `win_length` cells already holds discs of both.
+    ///
+    /// This assumes no disc is popped out again, and is never the case when
+    /// gravity flips as that can move a blocking disc out of the way.
+    pub fn is_forced_draw(&self) -> bool {
+        if self.flips() {
+            return false;
+        }
+
+        self.window_counts(self.current_colour)
+            .all(|(mine, theirs)| mine > 0 && theirs > 0)
+    }
+
+    /// For every run of `win_length` cells on the board, how many discs of
+    /// `colour` and of its opponent it holds.
+    fn window_counts(&self, colour: Colour) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let len = self.win_length;
+
+        Column::<W>::all()
+            .flat_map(|c| (0..H as u8).map(move |r| (c, r)))
+            .flat_map(|(c, r)| DIRECTIONS.iter().map(move |&d| (c, r, d)))
+            .filter_map(move |(c, r, d)| self.state.window_counts(c, r, d, len, colour))
+    }
+
+    /// `Board::threat_map` for runs of the game's `win_length`.
+    pub fn threat_map(&self, colour: Colour) -> [[i32; W]; H] {
+        self.state.threat_map_for(colour, self.win_length)
+    }
+}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// How useful dropping a disc in each cell would be for `colour`, indexed
+    /// by row then column with row 0 at the bottom, for drawing as a heatmap.
+    ///
+    /// Every run of `WIN_LENGTH` cells through a cell a disc can be dropped
+    /// in adds to its score: a run holding only discs of `colour` by what it
+    /// would be worth with one more, and a run holding only the opponent's
+    /// by half that, so completing a line outscores blocking one. Cells a
+    /// disc can't be dropped in score zero. `Game::threat_map` uses the
+    /// game's own line length instead.
+    pub fn threat_map(&self, colour: Colour) -> [[i32; W]; H] {
+        self.threat_map_for(colour, WIN_LENGTH)
+    }
+
+    fn threat_map_for(&self, colour: Colour, len: usize) -> [[i32; W]; H] {
+        let mut map = [[0; W]; H];
+
+        let cells = self
+            .allowed_columns()
+            .into_iter()
+            .filter_map(|column| self.drop_cell(column));
+
+        for (column, row) in cells {
+            let mut score = 0;
+
+            for &(dx, dy) in &DIRECTIONS {
+                // every run of `len` cells in this direction containing the cell
+                for back in 0..len as i8 {
+                    let start = match (column.offset(-dx * back), row_offset::<H>(row, -dy * back))
+                    {
+                        (Some(c), Some(r)) => (c, r),
+                        _ => continue,
+                    };
+
+                    match self.window_counts(start.0, start.1, (dx, dy), len, colour) {
+                        Some((mine, 0)) => score += window_weight(mine + 1, len),
+                        Some((0, theirs)) => score += window_weight(theirs + 1, len) / 2,
+                        _ => (),
+                    }
+                }
+            }
+
+            map[row as usize][column.to_idx()] = score;
+        }
+
+        map
+    }
+
+    /// How many discs of `colour` and of its opponent the run of `len` cells
+    /// starting at `column`, `row` and heading in `direction` holds, if it
+    /// fits on the board without covering a blocked cell.
+    fn window_counts(
+        &self,
+        column: Column<W>,
+        row: u8,
+        (dx, dy): (i8, i8),
+        len: usize,
+        colour: Colour,
+    ) -> Option<(usize, usize)> {
+        let mut mine = 0;
+        let mut theirs = 0;
+
+        for i in 0..len as i8 {
+            let col = column.offset(dx * i)?;
+            let row = row_offset::<H>(row, dy * i)?;
+
+            // a line can't run through a blocked cell
+            if self.is_blocked(col, row) {
+                return None;
+            }
+
+            match self.piece_at(col, row) {
+                Some(p) if p == colour => mine += 1,
+                Some(_) => theirs += 1,
+                None => (),
+            }
+        }
+
+        Some((mine, theirs))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+    use crate::Classic;
+
+    fn play(moves: &str) -> Classic {
+        let mut game = Classic::new(Colour::Red, false);
+        for column in moves.chars() {
+            game.make_move(Column::try_from(column).unwrap()).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn open_threes_count_for_their_colour() {
+        // red's B to D along the bottom can be finished from A or E
+        let game = play("BBCCD");
+
+        let red = game.explain_eval(Colour::Red);
+        assert_eq!(red.threes, 2 * THREE_WEIGHT);
+        assert_eq!(red.lines, 0);
+        assert!(red.to_string().contains("open threes +200"), "{}", red);
+
+        let yellow = game.explain_eval(Colour::Yellow);
+        assert_eq!(yellow.threes, -2 * THREE_WEIGHT);
+        assert_eq!(yellow.score(), -red.score());
+    }
+}
@@ -0,0 +1,133 @@
+//! Scripts of moves for the fuzz targets in `fuzz/`, built with the `fuzzing`
+//! feature.
+
+use arbitrary::{Arbitrary, Unstructured};
+
+use crate::{Colour, Column, FlipPolicy, Game, Gravity, Move, Winner};
+
+const GRAVITIES: [Gravity; 4] = [Gravity::Down, Gravity::Up, Gravity::Left, Gravity::Right];
+
+/// One thing to try in a `Script`, with columns wrapped around to fit the
+/// board. Moves the game refuses are skipped.
+#[derive(Debug, Clone, Copy)]
+pub enum Step {
+    Drop(u8),
+    Pop(u8),
+    Undo,
+}
+
+impl<'a> Arbitrary<'a> for Step {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        // mostly drops, so that games get somewhere
+        Ok(match u.int_in_range(0..=7)? {
+            0..=5 => Step::Drop(u.arbitrary()?),
+            6 => Step::Pop(u.arbitrary()?),
+            _ => Step::Undo,
+        })
+    }
+}
+
+/// The settings to start a game with and the steps to play in it.
+#[derive(Debug, Clone)]
+pub struct Script {
+    pub first_player: Colour,
+    pub flipping: bool,
+    pub flip_interval: u8,
+    pub flip_policy: FlipPolicy,
+    pub gravity: Gravity,
+    pub win_length: usize,
+    pub pop_out: bool,
+    pub steps: Vec<Step>,
+}
+
+impl<'a> Arbitrary<'a> for Script {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let first_player = if u.arbitrary()? {
+            Colour::Red
+        } else {
+            Colour::Yellow
+        };
+
+        let flip_policy = match u.int_in_range(0..=2)? {
+            0 => FlipPolicy::Alternate,
+            1 => FlipPolicy::Random(u.arbitrary()?),
+            _ => FlipPolicy::Toward(*u.choose(&GRAVITIES)?),
+        };
+
+        Ok(Script {
+            first_player,
+            flipping: u.arbitrary()?,
+            flip_interval: u.int_in_range(0..=4)?,
+            flip_policy,
+            gravity: *u.choose(&GRAVITIES)?,
+            win_length: u.int_in_range(1..=6)?,
+            pop_out: u.arbitrary()?,
+            steps: u.arbitrary()?,
+        })
+    }
+}
+
+impl Script {
+    pub fn game<const W: usize, const H: usize>(&self) -> Game<W, H> {
+        Game::builder()
+            .first_player(self.first_player)
+            .flipping(self.flipping)
+            .flip_interval(self.flip_interval)
+            .flip_policy(self.flip_policy)
+            .gravity(self.gravity)
+            .win_length(self.win_length.min(W.max(H)))
+            .pop_out(self.pop_out)
+            .build()
+    }
+
+    /// Plays every step in a new game, checking the game with
+    /// `Game::assert_invariants` after each one.
+    pub fn run<const W: usize, const H: usize>(&self) -> Game<W, H> {
+        let mut game = self.game();
+        game.assert_invariants();
+
+        for &step in &self.steps {
+            // refused moves are fine, only panics count
+            match step {
+                Step::Drop(idx) => {
+                    let _ = game.make_move(Column(idx as usize % W));
+                }
+                Step::Pop(idx) => {
+                    let _ = game.make_move_ex(Move::Pop(Column(idx as usize % W)));
+                }
+                Step::Undo => {
+                    let _ = game.undo_move();
+                }
+            }
+
+            game.assert_invariants();
+        }
+
+        game
+    }
+}
+
+impl<const W: usize, const H: usize> Game<W, H> {
+    /// Panics unless the board passes `Board::check_invariants` and the
+    /// winner matches the lines on it.
+    pub fn assert_invariants(&self) {
+        if let Err(e) = self.state.check_invariants() {
+            panic!("{}", e);
+        }
+
+        let board = &self.state;
+        let lines = self.check_win_all();
+        match self.winner {
+            None => {
+                assert_eq!(lines, None, "an unfinished game has a line");
+                assert!(!board.is_full(), "an unfinished game has a full board");
+            }
+            Some(Winner::Tie) => assert!(
+                lines == Some(Winner::Tie) || (lines.is_none() && board.is_full()),
+                "a tie with {:?} on the board",
+                lines
+            ),
+            Some(winner) => assert_eq!(lines, Some(winner), "a win without a line"),
+        }
+    }
+}
@@ -0,0 +1,3232 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![feature(impl_trait_in_assoc_type)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+#[cfg(feature = "alloc")]
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use core::hash::{Hash, Hasher};
+#[cfg(feature = "std")]
+use std::collections::hash_map::DefaultHasher;
+
+use bitvec::prelude::*;
+
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod bitboard;
+#[cfg(feature = "std")]
+mod book;
+#[cfg(feature = "std")]
+mod bot;
+mod builder;
+#[cfg(feature = "alloc")]
+mod bytes;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "alloc")]
+mod dataset;
+#[cfg(feature = "std")]
+mod dot;
+#[cfg(feature = "std")]
+mod driver;
+#[cfg(feature = "alloc")]
+mod eval;
+#[cfg(feature = "fuzzing")]
+mod fuzzing;
+#[cfg(feature = "std")]
+mod mcts;
+#[cfg(feature = "std")]
+mod net;
+#[cfg(feature = "alloc")]
+mod notation;
+#[cfg(feature = "alloc")]
+mod perft;
+#[cfg(feature = "testing")]
+mod random;
+#[cfg(feature = "std")]
+mod render;
+#[cfg(feature = "alloc")]
+mod replay;
+#[cfg(feature = "std")]
+mod save;
+#[cfg(all(feature = "serde", feature = "alloc"))]
+mod serialization;
+#[cfg(feature = "std")]
+mod series;
+#[cfg(feature = "alloc")]
+mod svg;
+#[cfg(feature = "std")]
+mod tablebase;
+#[cfg(feature = "alloc")]
+mod tactics;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+#[cfg(feature = "std")]
+pub use book::OpeningBook;
+#[cfg(feature = "std")]
+pub use bot::{bot_analysis, expected_line, Bot, Difficulty, SearchStats};
+pub use builder::GameBuilder;
+#[cfg(feature = "alloc")]
+pub use bytes::BytesError;
+#[cfg(feature = "std")]
+pub use cache::AnalysisCache;
+#[cfg(feature = "alloc")]
+pub use dataset::Record;
+#[cfg(feature = "std")]
+pub use dot::game_tree_dot;
+#[cfg(feature = "std")]
+pub use driver::{BotConfig, BotTurn, Driver, IllegalBotMove, PlayerKind, TimeControl};
+#[cfg(feature = "alloc")]
+pub use eval::{EvalBreakdown, Fitness};
+#[cfg(feature = "fuzzing")]
+pub use fuzzing::{Script, Step};
+#[cfg(feature = "std")]
+pub use mcts::Mcts;
+#[cfg(feature = "std")]
+pub use net::{host, join, NetError, NetGame};
+#[cfg(feature = "alloc")]
+pub use notation::{InvalidBoard, ParseError};
+#[cfg(feature = "alloc")]
+pub use perft::{perft, perft_detailed};
+#[cfg(feature = "std")]
+pub use render::{ColoredBoard, GlyphTheme, RenderOptions};
+#[cfg(feature = "alloc")]
+pub use replay::ReplayIter;
+#[cfg(feature = "std")]
+pub use save::{write_move, LoadError};
+#[cfg(feature = "std")]
+pub use series::{Match, MatchSide, RecordError};
+#[cfg(feature = "std")]
+pub use tablebase::{Outcome, Tablebase, Value};
+#[cfg(feature = "wasm")]
+pub use wasm::WasmGame;
+
+pub const BOARD_HEIGHT: usize = 6;
+pub const BOARD_WIDTH: usize = 7;
+pub const WIN_LENGTH: usize = 4;
+
+// `bitarr![for W * H]` can't be written against const parameters without
+// `generic_const_exprs`, so boards share storage sized for the largest one
+const MAX_CELLS: usize = 128;
+// columns are labelled with a single letter
+const MAX_WIDTH: usize = 26;
+
+pub type Classic = Game<BOARD_WIDTH, BOARD_HEIGHT>;
+pub type ClassicBoard = Board<BOARD_WIDTH, BOARD_HEIGHT>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Colour {
+    Red,
+    Yellow,
+}
+
+impl Colour {
+    pub fn all() -> &'static [Colour; 2] {
+        &[Colour::Red, Colour::Yellow]
+    }
+
+    pub fn invert(self) -> Self {
+        match self {
+            Colour::Red => Colour::Yellow,
+            Colour::Yellow => Colour::Red,
+        }
+    }
+
+    fn to_bool(self) -> bool {
+        match self {
+            Colour::Red => true,
+            Colour::Yellow => false,
+        }
+    }
+
+    fn from_bool(v: bool) -> Colour {
+        if v {
+            Colour::Red
+        } else {
+            Colour::Yellow
+        }
+    }
+}
+
+impl core::fmt::Display for Colour {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let col = match self {
+            Colour::Red => "R",
+            Colour::Yellow => "Y",
+        };
+
+        write!(f, "{}", col)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Winner {
+    Red,
+    Yellow,
+    Tie,
+}
+
+impl Winner {
+    pub fn from_colour(colour: Colour) -> Self {
+        match colour {
+            Colour::Red => Winner::Red,
+            Colour::Yellow => Winner::Yellow,
+        }
+    }
+
+    pub fn to_colour(self) -> Option<Colour> {
+        match self {
+            Winner::Red => Some(Colour::Red),
+            Winner::Yellow => Some(Colour::Yellow),
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Display for Winner {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Winner::Red => write!(f, "Red wins"),
+            Winner::Yellow => write!(f, "Yellow wins"),
+            Winner::Tie => write!(f, "Draw"),
+        }
+    }
+}
+
+pub(crate) fn colour_name(colour: Colour) -> &'static str {
+    match colour {
+        Colour::Red => "Red",
+        Colour::Yellow => "Yellow",
+    }
+}
+
+/// `None` being a tie.
+impl From<Option<Colour>> for Winner {
+    fn from(colour: Option<Colour>) -> Self {
+        colour.map_or(Winner::Tie, Winner::from_colour)
+    }
+}
+
+/// The wall that discs settle against.
+///
+/// With sideways gravity a disc dropped in a column slides along the row with
+/// that column's index instead, so column A is the bottom row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Gravity {
+    Down,
+    Up,
+    Left,
+    Right,
+}
+
+impl Gravity {
+    /// Gravity pulling toward the opposite wall.
+    pub fn invert(self) -> Self {
+        match self {
+            Gravity::Down => Gravity::Up,
+            Gravity::Up => Gravity::Down,
+            Gravity::Left => Gravity::Right,
+            Gravity::Right => Gravity::Left,
+        }
+    }
+
+    fn is_vertical(self) -> bool {
+        matches!(self, Gravity::Down | Gravity::Up)
+    }
+}
+
+/// Which way gravity points after each flip.
+///
+/// Every flip either leaves gravity alone or points it at the opposite
+/// wall, so that `undo_move` can put the discs back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlipPolicy {
+    /// Gravity points at the opposite wall every flip.
+    #[default]
+    Alternate,
+    /// Each flip has an even chance of pointing gravity at the opposite wall
+    /// or leaving it alone, decided by the seed and how many flips came
+    /// before so a game replays the same way.
+    Random(u64),
+    /// The first flip points gravity at this wall if it started pointing at
+    /// the opposite one, and it stays there.
+    Toward(Gravity),
+}
+
+/// splitmix64, which is enough to turn a seed and a count into random bits.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// A column index on a board `W` columns wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    all(feature = "serde", feature = "alloc"),
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "usize", into = "usize")
+)]
+pub struct Column<const W: usize>(usize);
+
+impl<const W: usize> core::fmt::Display for Column<W> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", (b'A' + self.0 as u8) as char)
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseColumnError {
+    Empty,
+    Invalid(String),
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for ParseColumnError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseColumnError::Empty => write!(f, "no column given"),
+            ParseColumnError::Invalid(s) => write!(f, "{:?} is not a column", s),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseColumnError {}
+
+#[cfg(feature = "alloc")]
+impl<const W: usize> TryFrom<char> for Column<W> {
+    type Error = ParseColumnError;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        let upper = c.to_ascii_uppercase();
+
+        if upper.is_ascii_uppercase() {
+            if let Some(column) = Column::new((upper as u8 - b'A') as usize) {
+                return Ok(column);
+            }
+        }
+
+        Err(ParseColumnError::Invalid(c.to_string()))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const W: usize> TryFrom<usize> for Column<W> {
+    type Error = ParseColumnError;
+
+    fn try_from(idx: usize) -> Result<Self, Self::Error> {
+        Column::new(idx).ok_or_else(|| ParseColumnError::Invalid(idx.to_string()))
+    }
+}
+
+impl<const W: usize> From<Column<W>> for usize {
+    fn from(column: Column<W>) -> Self {
+        column.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const W: usize> core::str::FromStr for Column<W> {
+    type Err = ParseColumnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut chars = s.chars();
+
+        match (chars.next(), chars.next()) {
+            (None, _) => Err(ParseColumnError::Empty),
+            (Some(c), None) => Column::try_from(c),
+            _ => Err(ParseColumnError::Invalid(s.to_owned())),
+        }
+    }
+}
+
+impl Column<BOARD_WIDTH> {
+    pub const A: Self = Column(0);
+    pub const B: Self = Column(1);
+    pub const C: Self = Column(2);
+    pub const D: Self = Column(3);
+    pub const E: Self = Column(4);
+    pub const F: Self = Column(5);
+    pub const G: Self = Column(6);
+}
+
+impl<const W: usize> Column<W> {
+    pub fn new(idx: usize) -> Option<Self> {
+        if idx < W {
+            Some(Column(idx))
+        } else {
+            None
+        }
+    }
+
+    pub fn to_idx(self) -> usize {
+        self.0
+    }
+
+    fn offset(self, offset: i8) -> Option<Self> {
+        let v = self.0 as i16 + offset as i16;
+        if v < 0 || v >= W as i16 {
+            None
+        } else {
+            Some(Column(v as usize))
+        }
+    }
+
+    // fn succ(self) -> Option<Column> {
+    //     self.offset(1)
+    // }
+
+    // fn pred(self) -> Option<Column> {
+    //     self.offset(-1)
+    // }
+
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..W).map(Column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    all(feature = "serde", feature = "alloc"),
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        into = "serialization::BoardRepr",
+        try_from = "serialization::BoardRepr"
+    )
+)]
+pub struct Board<const W: usize, const H: usize> {
+    present: bitarr![for MAX_CELLS],
+    tiles: bitarr![for MAX_CELLS],
+    gravity: Gravity,
+    /// The columns that aren't full, kept up to date by `set_cell`.
+    playable: bitarr![for MAX_WIDTH],
+    /// How many discs are settled in each lane a disc can be dropped in,
+    /// also kept up to date by `set_cell`.
+    heights: [u8; MAX_WIDTH],
+    /// Cells that never hold a disc, which discs settle past as if they
+    /// weren't there, so they're never part of a line either.
+    blocked: bitarr![for MAX_CELLS],
+    /// Red's then yellow's discs as bitboards, kept up to date by `set_cell`
+    /// on boards small enough for one.
+    bitboards: [u64; 2],
+}
+
+pub struct AllowedColumnsIterator<const W: usize> {
+    allowed: bitarr![for MAX_WIDTH],
+}
+
+impl<const W: usize> AllowedColumnsIterator<W> {
+    fn from_board<const H: usize>(board: &Board<W, H>) -> Self {
+        Self {
+            allowed: board.playable,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn new_empty() -> Self {
+        Self {
+            allowed: Default::default(),
+        }
+    }
+}
+
+impl<const W: usize> IntoIterator for AllowedColumnsIterator<W> {
+    type Item = Column<W>;
+
+    type IntoIter = impl Iterator<Item = Column<W>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.allowed
+            .into_iter()
+            .take(W)
+            .enumerate()
+            .filter_map(|(idx, c)| if c { Some(Column(idx)) } else { None })
+    }
+}
+
+/// The same columns as `AllowedColumnsIterator`, going out from the centre
+/// with the left of each pair first, eg. `D, C, E, B, F, A, G`. The strongest
+/// moves are usually central, so searching them first lets alpha-beta prune
+/// more of the tree.
+pub struct OrderedColumnsIterator<const W: usize> {
+    allowed: AllowedColumnsIterator<W>,
+    /// Bit `n` puts the right of the `n`th pair out from the centre first
+    /// instead, which the bot uses to break ties between equally good moves.
+    swaps: u32,
+}
+
+impl<const W: usize> IntoIterator for OrderedColumnsIterator<W> {
+    type Item = Column<W>;
+
+    type IntoIter = impl Iterator<Item = Column<W>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let (allowed, swaps) = (self.allowed.allowed, self.swaps);
+
+        // twice the distance from the centre, which is a whole number even
+        // when the centre falls between two columns
+        ((W - 1) % 2..W)
+            .step_by(2)
+            .enumerate()
+            .flat_map(move |(pair, distance)| {
+                let (mut first, mut second) = ((W - 1 - distance) / 2, (W - 1 + distance) / 2);
+                if swaps >> pair & 1 == 1 {
+                    core::mem::swap(&mut first, &mut second);
+                }
+
+                core::iter::once(first).chain(Some(second).filter(|&second| second != first))
+            })
+            .filter(move |&idx| allowed[idx])
+            .map(Column)
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Board<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    const FITS: () = assert!(
+        W * H <= MAX_CELLS && W <= MAX_WIDTH && H <= u8::MAX as usize,
+        "board dimensions too large"
+    );
+
+    pub fn new() -> Self {
+        Self::with_gravity(Gravity::Down)
+    }
+
+    pub fn with_gravity(gravity: Gravity) -> Self {
+        let () = Self::FITS;
+
+        let mut board = Self {
+            present: Default::default(),
+            tiles: Default::default(),
+            gravity,
+            playable: Default::default(),
+            heights: [0; MAX_WIDTH],
+            blocked: Default::default(),
+            bitboards: [0; 2],
+        };
+
+        for lane in 0..board.lane_count().min(W) {
+            let playable = board.lane_len(lane) > 0;
+            board.playable.set(lane, playable);
+        }
+
+        board
+    }
+
+    /// Whether the cell can never hold a disc, as set up with
+    /// `GameBuilder::block` or `GameBuilder::column_heights`.
+    pub fn is_blocked(&self, column: Column<W>, row: u8) -> bool {
+        self.blocked[Self::index_of(column, row)]
+    }
+
+    /// Stops discs ever going in an empty cell.
+    fn block(&mut self, column: Column<W>, row: u8) -> Result<(), BlockError<W>> {
+        if row as usize >= H {
+            return Err(BlockError::OffBoard(column, row));
+        }
+        if self.piece_at(column, row).is_some() {
+            return Err(BlockError::Occupied(column, row));
+        }
+
+        // the cell was past the top of its stack, so no disc has to move,
+        // but its lane may now be full
+        self.blocked.set(Self::index_of(column, row), true);
+        for lane in 0..self.lane_count().min(W) {
+            let playable = self.lane_height(lane) < self.lane_len(lane);
+            self.playable.set(lane, playable);
+        }
+
+        Ok(())
+    }
+
+    pub fn gravity(&self) -> Gravity {
+        self.gravity
+    }
+
+    /// How many discs are stacked where one dropped in `column` would fall.
+    pub fn column_height(&self, column: Column<W>) -> u8 {
+        if column.to_idx() < self.lane_count() {
+            self.lane_height(column.to_idx()) as u8
+        } else {
+            0
+        }
+    }
+
+    /// Whether a disc can't be dropped in `column`, which is always the case
+    /// for columns past the top row under sideways gravity.
+    pub fn column_full(&self, column: Column<W>) -> bool {
+        let lane = column.to_idx();
+        lane >= self.lane_count() || self.lane_height(lane) >= self.lane_len(lane)
+    }
+
+    fn index_of(column: Column<W>, height: u8) -> usize {
+        column.to_idx() * H + height as usize
+    }
+
+    // discs settle along lanes running away from the wall gravity points at,
+    // which are the columns under vertical gravity and the rows otherwise.
+    // Depths along a lane skip blocked cells
+
+    fn lane_count(&self) -> usize {
+        if self.gravity.is_vertical() {
+            W
+        } else {
+            H
+        }
+    }
+
+    /// How many cells long each lane is, blocked or not.
+    fn lane_slots(&self) -> usize {
+        if self.gravity.is_vertical() {
+            H
+        } else {
+            W
+        }
+    }
+
+    /// How many cells along `lane` can hold a disc.
+    fn lane_len(&self, lane: usize) -> usize {
+        if self.blocked.not_any() {
+            self.lane_slots()
+        } else {
+            (0..self.lane_slots())
+                .filter(|&offset| !self.is_blocked_slot(lane, offset))
+                .count()
+        }
+    }
+
+    /// The cell `offset` cells out from the wall along `lane`, blocked or
+    /// not.
+    fn lane_slot(&self, lane: usize, offset: usize) -> (Column<W>, u8) {
+        match self.gravity {
+            Gravity::Down | Gravity::Up => (Column(lane), self.physical_row(offset as u8)),
+            Gravity::Left => (Column(offset), lane as u8),
+            Gravity::Right => (Column(W - 1 - offset), lane as u8),
+        }
+    }
+
+    fn is_blocked_slot(&self, lane: usize, offset: usize) -> bool {
+        let (column, row) = self.lane_slot(lane, offset);
+        self.is_blocked(column, row)
+    }
+
+    /// The `depth`th cell out from the wall along `lane` that isn't blocked.
+    fn lane_cell(&self, lane: usize, depth: usize) -> (Column<W>, u8) {
+        if self.blocked.not_any() {
+            return self.lane_slot(lane, depth);
+        }
+
+        let offset = (0..self.lane_slots())
+            .filter(|&offset| !self.is_blocked_slot(lane, offset))
+            .nth(depth)
+            .unwrap();
+
+        self.lane_slot(lane, offset)
+    }
+
+    fn lane_height(&self, lane: usize) -> usize {
+        if lane < W {
+            self.heights[lane] as usize
+        } else {
+            self.scan_lane_height(lane)
+        }
+    }
+
+    /// `lane_height` counted from the cells, for keeping it up to date.
+    fn scan_lane_height(&self, lane: usize) -> usize {
+        (0..self.lane_len(lane))
+            .take_while(|&depth| {
+                let (column, row) = self.lane_cell(lane, depth);
+                self.present[Self::index_of(column, row)]
+            })
+            .count()
+    }
+
+    fn lane_piece(&self, lane: usize, depth: usize) -> Option<Colour> {
+        let (column, row) = self.lane_cell(lane, depth);
+        self.piece_at(column, row)
+    }
+
+    fn set_lane_piece(&mut self, lane: usize, depth: usize, disc: Option<Colour>) {
+        let (column, row) = self.lane_cell(lane, depth);
+        self.set_cell(column, row, disc);
+    }
+
+    /// The discs in `lane`, starting from the wall.
+    #[cfg(all(feature = "serde", feature = "alloc"))]
+    fn lane_discs(&self, lane: usize) -> Vec<Colour> {
+        (0..self.lane_height(lane))
+            .filter_map(|depth| {
+                let (column, row) = self.lane_cell(lane, depth);
+                self.piece_at(column, row)
+            })
+            .collect()
+    }
+
+    /// Replaces the discs in `lane`, starting from the wall.
+    #[cfg(all(feature = "serde", feature = "alloc"))]
+    fn set_lane(&mut self, lane: usize, discs: &[Colour]) {
+        for depth in 0..self.lane_len(lane) {
+            let (column, row) = self.lane_cell(lane, depth);
+            self.set_cell(column, row, discs.get(depth).copied());
+        }
+    }
+
+    fn set_cell(&mut self, column: Column<W>, row: u8, disc: Option<Colour>) {
+        let idx = Self::index_of(column, row);
+        self.present.set(idx, disc.is_some());
+        self.tiles.set(idx, matches!(disc, Some(c) if c.to_bool()));
+        self.set_bitboard_cell(column, row, disc);
+
+        let lane = if self.gravity.is_vertical() {
+            column.to_idx()
+        } else {
+            row as usize
+        };
+        if lane < W {
+            let height = self.scan_lane_height(lane);
+            self.heights[lane] = height as u8;
+            let playable = height < self.lane_len(lane);
+            self.playable.set(lane, playable);
+        }
+    }
+
+    /// The row a disc `logical_height` cells out from the floor or ceiling
+    /// gravity points at sits on. Sideways gravity doesn't move rows around,
+    /// so they're left as they are.
+    pub fn physical_row(&self, logical_height: u8) -> u8 {
+        match self.gravity {
+            Gravity::Up => H as u8 - 1 - logical_height,
+            _ => logical_height,
+        }
+    }
+
+    /// The inverse of `physical_row`, how far out from the floor or ceiling
+    /// gravity points at `physical_row` is.
+    pub fn logical_height(&self, physical_row: u8) -> u8 {
+        // flipping rows over is its own inverse
+        self.physical_row(physical_row)
+    }
+
+    /// The cells discs dropped in `column` fill, in the order they fill them
+    /// outward from the wall gravity points at, skipping blocked cells.
+    /// Under sideways gravity they run along the row with `column`'s index,
+    /// and columns past the top row fill nothing.
+    pub fn fill_order(&self, column: Column<W>) -> impl Iterator<Item = (Column<W>, u8)> + '_ {
+        let lane = column.to_idx();
+        let len = if lane < self.lane_count() {
+            self.lane_len(lane)
+        } else {
+            0
+        };
+
+        (0..len).map(move |depth| self.lane_cell(lane, depth))
+    }
+
+    /// The cell a disc dropped in `column` would land in, if it isn't full.
+    pub fn drop_cell(&self, column: Column<W>) -> Option<(Column<W>, u8)> {
+        if self.column_full(column) {
+            return None;
+        }
+
+        Some(self.lane_cell(column.to_idx(), self.lane_height(column.to_idx())))
+    }
+
+    /// The row a disc dropped in `column` would land on, if it isn't full.
+    pub fn drop_row(&self, column: Column<W>) -> Option<u8> {
+        self.drop_cell(column).map(|(_, row)| row)
+    }
+
+    fn place_in_lane(&mut self, lane: usize, colour: Colour) -> (Column<W>, u8) {
+        let (column, row) = self.lane_cell(lane, self.lane_height(lane));
+        self.set_cell(column, row, Some(colour));
+
+        (column, row)
+    }
+
+    /// Drops a disc, returning the cell it landed in.
+    fn place_on_column(&mut self, column: Column<W>, colour: Colour) -> (Column<W>, u8) {
+        assert!(!self.column_full(column), "placed a disc on a full column");
+
+        let cell = self.place_in_lane(column.to_idx(), colour);
+        #[cfg(feature = "alloc")]
+        debug_assert_eq!(self.check_invariants(), Ok(()));
+
+        cell
+    }
+
+    #[cfg(feature = "alloc")]
+    fn remove_from_column(&mut self, column: Column<W>) {
+        let lane = column.to_idx();
+        let (column, row) = self.lane_cell(lane, self.lane_height(lane) - 1);
+        self.set_cell(column, row, None);
+        debug_assert_eq!(self.check_invariants(), Ok(()));
+    }
+
+    /// The disc resting against the wall where `column` drops to.
+    fn wall_piece(&self, column: Column<W>) -> Option<Colour> {
+        if column.to_idx() >= self.lane_count() {
+            return None;
+        }
+
+        let (column, row) = self.lane_cell(column.to_idx(), 0);
+        self.piece_at(column, row)
+    }
+
+    /// Takes the disc resting against the wall out of a column, letting the
+    /// rest of the stack fall into its place.
+    fn pop_from_column(&mut self, column: Column<W>) {
+        let lane = column.to_idx();
+        let height = self.lane_height(lane);
+
+        for depth in 1..height {
+            self.set_lane_piece(lane, depth - 1, self.lane_piece(lane, depth));
+        }
+        self.set_lane_piece(lane, height - 1, None);
+        #[cfg(feature = "alloc")]
+        debug_assert_eq!(self.check_invariants(), Ok(()));
+    }
+
+    /// The reverse of `pop_from_column`, sliding a disc in underneath a stack.
+    #[cfg(feature = "alloc")]
+    fn push_under_column(&mut self, column: Column<W>, colour: Colour) {
+        let lane = column.to_idx();
+
+        for depth in (0..self.lane_height(lane)).rev() {
+            self.set_lane_piece(lane, depth + 1, self.lane_piece(lane, depth));
+        }
+        self.set_lane_piece(lane, 0, Some(colour));
+        debug_assert_eq!(self.check_invariants(), Ok(()));
+    }
+
+    /// Points gravity at the opposite wall, letting every disc fall to it.
+    /// Flipping twice gives back exactly the same board, which `undo_move`
+    /// relies on.
+    fn flip_gravity(&mut self) {
+        *self = self.flipped();
+    }
+
+    /// The board after gravity flips to point at the opposite wall, leaving
+    /// this one as it is, eg. to preview a flip.
+    pub fn flipped(&self) -> Self {
+        let mut board = self.clone();
+        board.gravity = board.gravity.invert();
+
+        // each stack now rests against the far end of its lane, with the disc
+        // that was furthest from the old wall nearest the new one, so it only
+        // has to slide across
+        for lane in 0..board.lane_count() {
+            let len = board.lane_len(lane);
+            let discs = (0..len)
+                .filter(|&depth| board.lane_piece(lane, depth).is_some())
+                .count();
+
+            for depth in 0..discs {
+                board.set_lane_piece(lane, depth, board.lane_piece(lane, len - discs + depth));
+            }
+            for depth in discs..len {
+                board.set_lane_piece(lane, depth, None);
+            }
+        }
+
+        #[cfg(feature = "alloc")]
+        debug_assert_eq!(board.check_invariants(), Ok(()));
+
+        board
+    }
+
+    /// Checks that every disc rests against the wall gravity points at or on
+    /// another disc (past any blocked cells), that no blocked cell holds a
+    /// disc, that the cached heights and playable columns match the
+    /// discs, that only cells with a disc in them are coloured, which
+    /// `count_pieces_of` relies on, and that the bitboards hold the same
+    /// discs as the cells. Moves check this in debug builds.
+    #[cfg(feature = "alloc")]
+    pub fn check_invariants(&self) -> Result<(), String> {
+        for lane in 0..self.lane_count() {
+            let height = self.lane_height(lane);
+
+            if let Some(depth) =
+                (height..self.lane_len(lane)).find(|&depth| self.lane_piece(lane, depth).is_some())
+            {
+                let (column, row) = self.lane_cell(lane, depth);
+                return Err(format!(
+                    "the disc in column {} row {} isn't supported, as only {} discs are settled \
+                     in its lane",
+                    column, row, height
+                ));
+            }
+        }
+
+        for lane in 0..self.lane_count().min(W) {
+            if self.heights[lane] as usize != self.scan_lane_height(lane) {
+                return Err(format!(
+                    "lane {} is cached as {} discs high but holds {}",
+                    lane,
+                    self.heights[lane],
+                    self.scan_lane_height(lane)
+                ));
+            }
+        }
+
+        for column in Column::<W>::all() {
+            let playable = !self.column_full(column);
+
+            if self.playable[column.to_idx()] != playable {
+                return Err(format!(
+                    "column {} is cached as {}playable",
+                    column,
+                    if playable { "un" } else { "" }
+                ));
+            }
+        }
+
+        if (W..MAX_WIDTH).any(|idx| self.playable[idx]) {
+            return Err("a column past the edge of the board is cached as playable".to_owned());
+        }
+
+        for idx in 0..MAX_CELLS {
+            if idx >= W * H && self.present[idx] {
+                return Err(format!("cell {} is past the edge of the board", idx));
+            }
+
+            if self.blocked[idx] && self.present[idx] {
+                return Err(format!("cell {} is blocked but holds a disc", idx));
+            }
+
+            if self.tiles[idx] && !self.present[idx] {
+                return Err(format!("cell {} is coloured red without a disc", idx));
+            }
+        }
+
+        for &colour in Colour::all() {
+            if self.bitboard(colour) != self.scan_bitboard(colour) {
+                return Err(format!("the {} bitboard doesn't match the board", colour));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn piece_at(&self, column: Column<W>, height: u8) -> Option<Colour> {
+        let idx = Self::index_of(column, height);
+        if self.present[idx] {
+            Some(Colour::from_bool(self.tiles[idx]))
+        } else {
+            None
+        }
+    }
+
+    /// Like `piece_at`, but returns `None` for rows outside the board rather
+    /// than reading into the neighbouring column.
+    pub fn get(&self, column: Column<W>, row: u8) -> Option<Colour> {
+        if row as usize >= H {
+            return None;
+        }
+
+        self.piece_at(column, row)
+    }
+
+    /// The discs in `column` from the bottom row up, whichever way gravity
+    /// points. Under sideways gravity a column can have gaps, which are
+    /// skipped.
+    #[cfg(feature = "alloc")]
+    pub fn column_stack(&self, column: Column<W>) -> Vec<Colour> {
+        (0..H as u8)
+            .filter_map(|row| self.piece_at(column, row))
+            .collect()
+    }
+
+    /// Every cell on the board and what's in it, going up each column in
+    /// turn from A.
+    pub fn iter_cells(&self) -> impl Iterator<Item = (Column<W>, u8, Option<Colour>)> + '_ {
+        Column::all()
+            .flat_map(|column| (0..H as u8).map(move |row| (column, row)))
+            .map(move |(column, row)| (column, row, self.piece_at(column, row)))
+    }
+
+    pub fn count_pieces(&self) -> usize {
+        self.present.count_ones()
+    }
+
+    pub fn count_pieces_of(&self, colour: Colour) -> usize {
+        // only the cells holding a red disc are set in `tiles`
+        let red = self.tiles.count_ones();
+
+        match colour {
+            Colour::Red => red,
+            Colour::Yellow => self.count_pieces() - red,
+        }
+    }
+
+    /// The most discs of `colour` in a row in any direction, however many it
+    /// takes to win.
+    pub fn longest_run(&self, colour: Colour) -> usize {
+        let mut longest = 0;
+
+        for (column, row, disc) in self.iter_cells() {
+            if disc != Some(colour) {
+                continue;
+            }
+
+            for &(dx, dy) in &[(1, 0), (0, 1), (1, 1), (1, -1)] {
+                // only count each run from its first disc
+                if self.run_length(column, row, (-dx, -dy), colour) > 1 {
+                    continue;
+                }
+
+                longest = longest.max(self.run_length(column, row, (dx, dy), colour));
+            }
+        }
+
+        longest
+    }
+
+    /// How many discs of `colour` there are in a row from `column`, `row`
+    /// heading in `direction`, counting the one there.
+    fn run_length(&self, column: Column<W>, row: u8, (dx, dy): (i8, i8), colour: Colour) -> usize {
+        (0..)
+            .map_while(|depth: i8| {
+                Some((
+                    column.offset(dx * depth)?,
+                    row_offset::<H>(row, dy * depth)?,
+                ))
+            })
+            .take_while(|&(c, r)| self.piece_at(c, r) == Some(colour))
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.present.not_any()
+    }
+
+    pub fn is_full(&self) -> bool {
+        Column::all().all(|col| self.column_full(col))
+    }
+
+    pub fn allowed_columns(&self) -> AllowedColumnsIterator<W> {
+        AllowedColumnsIterator::from_board(self)
+    }
+
+    pub fn ordered_columns(&self) -> OrderedColumnsIterator<W> {
+        OrderedColumnsIterator {
+            allowed: self.allowed_columns(),
+            swaps: 0,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn render(&self) {
+        println!("{}", self);
+    }
+
+    /// The board reflected left to right, so column A swaps with the last.
+    pub fn mirror(&self) -> Self {
+        let mut mirrored = self.clone();
+
+        mirrored.gravity = match self.gravity {
+            Gravity::Left | Gravity::Right => self.gravity.invert(),
+            vertical => vertical,
+        };
+
+        for column in Column::all() {
+            let from = Column(W - 1 - column.to_idx());
+
+            for row in 0..H as u8 {
+                mirrored
+                    .blocked
+                    .set(Self::index_of(column, row), self.is_blocked(from, row));
+            }
+        }
+
+        for column in Column::all() {
+            let from = Column(W - 1 - column.to_idx());
+
+            for row in 0..H as u8 {
+                mirrored.set_cell(column, row, self.piece_at(from, row));
+            }
+        }
+
+        mirrored
+    }
+
+    /// The board turned half way round, so the cell in column A row 0 swaps
+    /// with the one in the last column and top row. Gravity turns with it,
+    /// so discs still rest where they did and it plays out just the same.
+    pub fn rotate_180(&self) -> Self {
+        let mut rotated = self.clone();
+        rotated.gravity = self.gravity.invert();
+
+        let from =
+            |column: Column<W>, row: u8| (Column(W - 1 - column.to_idx()), H as u8 - 1 - row);
+
+        // blocked cells first, as the lanes discs go in skip them
+        for column in Column::all() {
+            for row in 0..H as u8 {
+                let (c, r) = from(column, row);
+                rotated
+                    .blocked
+                    .set(Self::index_of(column, row), self.is_blocked(c, r));
+            }
+        }
+
+        for column in Column::all() {
+            for row in 0..H as u8 {
+                let (c, r) = from(column, row);
+                rotated.set_cell(column, row, self.piece_at(c, r));
+            }
+        }
+
+        #[cfg(feature = "alloc")]
+        debug_assert_eq!(rotated.check_invariants(), Ok(()));
+
+        rotated
+    }
+
+    /// A hash that's the same for a board and its mirror, which play out
+    /// identically, for keying transposition tables.
+    #[cfg(feature = "std")]
+    pub fn canonical_key(&self) -> u64 {
+        self.key().min(self.mirror().key())
+    }
+
+    /// The hash of just this board, not folded with its mirror.
+    #[cfg(feature = "std")]
+    fn key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// A hash of just where the discs are, so boards that only differ in
+    /// gravity share it.
+    ///
+    /// Use this to compare what's on the board, eg. across a flip. Boards
+    /// with the same layout but different gravity play out differently, so
+    /// anything keyed on what happens next should use the full `Hash` (or
+    /// `canonical_key`) instead.
+    #[cfg(feature = "std")]
+    pub fn layout_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.present.hash(&mut hasher);
+        self.tiles.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<const W: usize, const H: usize> core::fmt::Display for Board<W, H> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for i in (0..H).rev() {
+            for col in Column::<W>::all() {
+                match self.piece_at(col, i as u8) {
+                    Some(p) => write!(f, "{}", p)?,
+                    None if self.is_blocked(col, i as u8) => write!(f, "#")?,
+                    None => write!(f, "_")?,
+                };
+            }
+
+            writeln!(f)?;
+        }
+
+        for c in Column::<W>::all() {
+            write!(f, "{}", c)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn row_offset<const H: usize>(row: u8, offset: i8) -> Option<u8> {
+    let v = row as i16 + offset as i16;
+    let h = H as i16;
+    if v < 0 || v >= h {
+        None
+    } else {
+        Some(v as u8)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    all(feature = "serde", feature = "alloc"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Move<const W: usize> {
+    Drop(Column<W>),
+    /// Pop Out: remove your own disc from the bottom of a column.
+    Pop(Column<W>),
+    /// The swap rule: instead of answering the first move, the second player
+    /// takes it as their own, leaving the first player to move with the
+    /// other colour.
+    Swap,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveError<const W: usize> {
+    GameOver,
+    ColumnFull(Column<W>),
+    ColumnEmpty(Column<W>),
+    NotYourPiece(Column<W>),
+    /// A swap other than straight after the first move, or without the swap
+    /// rule.
+    CannotSwap,
+    /// A pop without the Pop Out rule.
+    CannotPop,
+    /// The player ran out of time under a `TimeControl`, losing the game.
+    OutOfTime,
+}
+
+impl<const W: usize> core::fmt::Display for MoveError<W> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MoveError::GameOver => write!(f, "the game is already over"),
+            MoveError::ColumnFull(c) => write!(f, "column {} is full", c),
+            MoveError::ColumnEmpty(c) => write!(f, "column {} is empty", c),
+            MoveError::NotYourPiece(c) => {
+                write!(f, "the disc at the bottom of column {} isn't yours", c)
+            }
+            MoveError::CannotSwap => write!(f, "sides can only be swapped after the first move"),
+            MoveError::CannotPop => write!(f, "discs can only be popped out under Pop Out rules"),
+            MoveError::OutOfTime => write!(f, "the time for the move ran out"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const W: usize> std::error::Error for MoveError<W> {}
+
+/// Why a cell couldn't be blocked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockError<const W: usize> {
+    /// The cell is above the top of the board.
+    OffBoard(Column<W>, u8),
+    /// The cell holds a disc.
+    Occupied(Column<W>, u8),
+}
+
+impl<const W: usize> core::fmt::Display for BlockError<W> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BlockError::OffBoard(c, row) => {
+                write!(f, "row {} of column {} is off the board", row, c)
+            }
+            BlockError::Occupied(c, row) => {
+                write!(f, "row {} of column {} holds a disc", row, c)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const W: usize> std::error::Error for BlockError<W> {}
+
+/// A game taken apart by `Game::into_parts`, eg. to keep its pieces in a
+/// frontend's own structs, and put back together by `Game::from_parts`.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct GameParts<const W: usize, const H: usize> {
+    pub board: Board<W, H>,
+    pub current_colour: Colour,
+    pub winner: Option<Winner>,
+    /// How many moves into the round the game is.
+    pub round: u8,
+    /// The rules the game was made with, who went first and the gravity it
+    /// started with. Blocked cells and the starting position are already on
+    /// `board`, so any set here are ignored.
+    pub settings: GameBuilder<W, H>,
+    /// Every move made, so the game can still be undone.
+    pub history: Vec<Move<W>>,
+}
+
+/// Why `Game::from_parts` turned down its parts.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartsError {
+    /// A round that can't be reached, past the flip interval, without
+    /// flipping at all, or that doesn't fit the number of moves made.
+    Round(u8),
+    /// The winner given, then the one the board has.
+    Winner(Option<Winner>, Option<Winner>),
+    /// The colour given to move, which can't be with the discs each side
+    /// has on the board.
+    Turn(Colour),
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Display for PartsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let result = |winner: &Option<Winner>| match winner {
+            Some(Winner::Red) => "red won",
+            Some(Winner::Yellow) => "yellow won",
+            Some(Winner::Tie) => "a tie",
+            None => "still going",
+        };
+
+        match self {
+            PartsError::Round(round) => write!(f, "round {} can't be reached", round),
+            PartsError::Winner(given, found) => write!(
+                f,
+                "the game was given as {} but the board has it {}",
+                result(given),
+                result(found)
+            ),
+            PartsError::Turn(colour) => write!(
+                f,
+                "it can't be {}'s turn with the discs on the board",
+                colour
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PartsError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndoError {
+    NoMoves,
+}
+
+impl core::fmt::Display for UndoError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            UndoError::NoMoves => write!(f, "no moves have been made"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UndoError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    all(feature = "serde", feature = "alloc"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Game<const W: usize, const H: usize> {
+    state: Board<W, H>,
+    /// The colour that moved first, which a swap doesn't change.
+    first_player: Colour,
+    current_colour: Colour,
+    winner: Option<Winner>,
+    flipping: bool,
+    flip_interval: u8,
+    flip_policy: FlipPolicy,
+    round: u8,
+    /// How many rounds have ended, each with a flip whether or not it moved
+    /// gravity.
+    intervals: u32,
+    start_gravity: Gravity,
+    /// Which of two columns the same distance from the centre a bot searches
+    /// first at each ply, zero always trying the left first. Set by seeded
+    /// bots on their own copy of the game.
+    #[cfg_attr(all(feature = "serde", feature = "alloc"), serde(skip))]
+    tie_break: u64,
+    swap_rule: bool,
+    /// Whether `Move::Pop` is allowed.
+    pop_out: bool,
+    swapped: bool,
+    /// Whether a line made by the last move of a round wins straight away,
+    /// rather than only if it's still there after the flip.
+    persist_wins_through_flip: bool,
+    ply: usize,
+    #[cfg(feature = "alloc")]
+    history: Vec<Move<W>>,
+    win_length: usize,
+}
+
+impl<const W: usize, const H: usize> Game<W, H> {
+    pub fn new(starting_colour: Colour, flipping: bool) -> Self {
+        Self::builder()
+            .first_player(starting_colour)
+            .flipping(flipping)
+            .build()
+    }
+
+    pub fn with_win_length(starting_colour: Colour, flipping: bool, win_length: usize) -> Self {
+        Self::builder()
+            .first_player(starting_colour)
+            .flipping(flipping)
+            .win_length(win_length)
+            .build()
+    }
+
+    pub fn builder() -> GameBuilder<W, H> {
+        GameBuilder::new()
+    }
+
+    /// The pieces of the game, for `from_parts`.
+    #[cfg(feature = "alloc")]
+    pub fn into_parts(self) -> GameParts<W, H> {
+        let settings = Self::builder()
+            .first_player(self.first_player)
+            .flipping(self.flipping)
+            .flip_interval(self.flip_interval)
+            .flip_policy(self.flip_policy)
+            .win_length(self.win_length)
+            .gravity(self.start_gravity)
+            .swap_rule(self.swap_rule)
+            .pop_out(self.pop_out)
+            .persist_wins_through_flip(self.persist_wins_through_flip);
+
+        GameParts {
+            board: self.state,
+            current_colour: self.current_colour,
+            winner: self.winner,
+            round: self.round,
+            settings,
+            history: self.history,
+        }
+    }
+
+    /// Puts a game back together from `into_parts`, checking that the winner
+    /// is the one the board has, that the colour to move fits the discs on
+    /// the board, and that the round can be reached after the moves made.
+    /// Resigned and agreed games can't be put back, as the board doesn't show
+    /// how they ended.
+    #[cfg(feature = "alloc")]
+    pub fn from_parts(parts: GameParts<W, H>) -> Result<Self, PartsError> {
+        let mut game = parts.settings.build();
+        game.state = parts.board;
+        game.current_colour = parts.current_colour;
+
+        let mut winner = game.check_win_all();
+        if winner.is_none() && game.state.is_full() {
+            winner = Some(Winner::Tie);
+        }
+        if winner != parts.winner {
+            return Err(PartsError::Winner(parts.winner, winner));
+        }
+        game.winner = winner;
+
+        // whoever has more discs just moved, though a pop takes one of the
+        // mover's own discs away
+        if !parts.history.iter().any(|mv| matches!(mv, Move::Pop(_))) {
+            let red = game.state.count_pieces_of(Colour::Red);
+            let yellow = game.state.count_pieces_of(Colour::Yellow);
+            let just_moved = match red.cmp(&yellow) {
+                core::cmp::Ordering::Greater => Some(Colour::Red),
+                core::cmp::Ordering::Less => Some(Colour::Yellow),
+                core::cmp::Ordering::Equal => None,
+            };
+
+            if red.abs_diff(yellow) > 1 || just_moved == Some(game.current_colour) {
+                return Err(PartsError::Turn(game.current_colour));
+            }
+        }
+
+        // every move but a swap is counted in the round, which starts again
+        // at each flip, and the move that wins the game is counted even if
+        // it ends the round
+        let counted = parts.history.iter().filter(|&&mv| mv != Move::Swap).count();
+        let round = parts.round;
+        let last_round = if winner.is_some() {
+            game.flip_interval
+        } else {
+            game.flip_interval.saturating_sub(1)
+        };
+
+        if game.flips() {
+            let started = counted.checked_sub(round as usize);
+            if round > last_round
+                || !started.is_some_and(|n| n.is_multiple_of(game.flip_interval as usize))
+            {
+                return Err(PartsError::Round(round));
+            }
+
+            game.intervals = (counted - round as usize) as u32 / game.flip_interval as u32;
+        } else if round > 0 {
+            return Err(PartsError::Round(round));
+        }
+        game.round = round;
+
+        game.ply = parts.history.len();
+        game.swapped = parts.history.contains(&Move::Swap);
+        game.history = parts.history;
+
+        Ok(game)
+    }
+
+    pub fn make_move(&mut self, column: Column<W>) -> Result<(), MoveError<W>> {
+        self.make_move_ex(Move::Drop(column))
+    }
+
+    /// The game after dropping a disc in `column`, leaving this one as it is.
+    pub fn with_move(&self, column: Column<W>) -> Result<Self, MoveError<W>> {
+        let mut game = self.clone();
+        game.make_move(column)?;
+        Ok(game)
+    }
+
+    /// Whether a disc can be dropped in `column`, with the error
+    /// `make_move` would give if not.
+    pub fn validate_move(&self, column: Column<W>) -> Result<(), MoveError<W>> {
+        if self.is_finished() {
+            Err(MoveError::GameOver)
+        } else if self.state.column_full(column) {
+            Err(MoveError::ColumnFull(column))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn make_move_ex(&mut self, mv: Move<W>) -> Result<(), MoveError<W>> {
+        // without Pop Out rules a pop is never a move, whatever the position
+        if matches!(mv, Move::Pop(_)) && !self.pop_out {
+            return Err(MoveError::CannotPop);
+        }
+        if self.is_finished() {
+            return Err(MoveError::GameOver);
+        }
+
+        match mv {
+            Move::Drop(column) => self.validate_move(column)?,
+            Move::Pop(column) => match self.state.wall_piece(column) {
+                None => return Err(MoveError::ColumnEmpty(column)),
+                Some(c) if c != self.current_colour => {
+                    return Err(MoveError::NotYourPiece(column));
+                }
+                Some(_) => (),
+            },
+            Move::Swap if !self.can_swap() => return Err(MoveError::CannotSwap),
+            Move::Swap => (),
+        }
+
+        #[cfg(feature = "alloc")]
+        self.history.push(mv);
+        self.ply += 1;
+
+        let placed = match mv {
+            Move::Drop(column) => Some(self.state.place_on_column(column, self.current_colour)),
+            Move::Pop(column) => {
+                self.state.pop_from_column(column);
+                None
+            }
+            // the same colour moves next, played by the other side
+            Move::Swap => {
+                self.swapped = true;
+                return Ok(());
+            }
+        };
+
+        self.current_colour = self.current_colour.invert();
+
+        // the round is counted even when this move wins so that `undo_move`
+        // can tell a flipping move apart from a winning one
+        if self.flips() {
+            self.round += 1;
+        }
+
+        // a pop shifts a whole column, so only a drop can be checked locally
+        let made_line = placed.and_then(|(column, height)| self.check_win(column, height));
+        if made_line.is_some() && self.persist_wins_through_flip {
+            self.winner = made_line;
+            return Ok(());
+        }
+
+        let round_over = self.flips() && self.round == self.flip_interval;
+        let flipped = round_over && self.flip_moves_gravity(self.intervals);
+
+        if round_over {
+            self.round = 0;
+            self.intervals += 1;
+        }
+        if flipped {
+            self.flip()
+        }
+
+        // the disc just dropped was the only one that could have made a line,
+        // unless discs were moved by a flip or pop
+        let winner = if flipped || placed.is_none() {
+            self.check_win_all()
+        } else {
+            made_line
+        };
+
+        if let Some(winner) = winner {
+            self.winner = Some(winner);
+        } else if self.state.is_full() {
+            // only a tie once we know no line exists anywhere on the board
+            self.winner = Some(Winner::Tie);
+        }
+
+        Ok(())
+    }
+
+    /// Ends the game with `who`'s opponent winning.
+    pub fn resign(&mut self, who: Colour) -> Result<(), MoveError<W>> {
+        if self.is_finished() {
+            return Err(MoveError::GameOver);
+        }
+
+        self.winner = Some(Winner::from_colour(who.invert()));
+        Ok(())
+    }
+
+    /// Ends the game as a tie, once both players have agreed to it.
+    pub fn agree_draw(&mut self) -> Result<(), MoveError<W>> {
+        if self.is_finished() {
+            return Err(MoveError::GameOver);
+        }
+
+        self.winner = Some(Winner::Tie);
+        Ok(())
+    }
+
+    /// Whether the current player can make a `Move::Swap`, which under the
+    /// swap rule is only straight after the first move.
+    pub fn can_swap(&self) -> bool {
+        self.swap_rule && self.ply == 1 && !self.is_finished()
+    }
+
+    /// Whether the second player swapped sides, so that they play the colour
+    /// that moved first.
+    pub fn swapped(&self) -> bool {
+        self.swapped
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn undo_move(&mut self) -> Result<Move<W>, UndoError> {
+        let mv = self.history.pop().ok_or(UndoError::NoMoves)?;
+        self.ply -= 1;
+
+        if mv == Move::Swap {
+            self.swapped = false;
+            return Ok(mv);
+        }
+
+        if self.flips() && self.round == 0 {
+            // this move ended a round, and if that flipped gravity flipping
+            // again puts every disc back against the wall it was placed
+            // against
+            self.intervals -= 1;
+            if self.flip_moves_gravity(self.intervals) {
+                self.flip();
+            }
+            self.round = self.flip_interval - 1;
+        } else if self.flips() {
+            self.round -= 1;
+        }
+
+        self.current_colour = self.current_colour.invert();
+
+        match mv {
+            Move::Drop(column) => self.state.remove_from_column(column),
+            Move::Pop(column) => self.state.push_under_column(column, self.current_colour),
+            Move::Swap => unreachable!("swaps are undone above"),
+        }
+
+        // usually there's no line left, but one made by a flip before the
+        // undone move still counts
+        self.winner = self.check_win_all();
+
+        Ok(mv)
+    }
+
+    /// The game as it was after its first `ply` moves, or `None` if fewer
+    /// have been made.
+    #[cfg(feature = "alloc")]
+    pub fn position_at(&self, ply: usize) -> Option<Self> {
+        if ply > self.ply() {
+            return None;
+        }
+
+        let mut game = self.clone();
+        for _ in ply..self.ply() {
+            game.undo_move().ok()?;
+        }
+
+        Some(game)
+    }
+
+    /// Whether gravity ever flips, an interval of zero turning it off.
+    fn flips(&self) -> bool {
+        self.flipping && self.flip_interval > 0
+    }
+
+    fn flip(&mut self) {
+        self.state.flip_gravity();
+    }
+
+    /// Whether the flip ending round `interval`, counting from 0, points
+    /// gravity at the opposite wall.
+    fn flip_moves_gravity(&self, interval: u32) -> bool {
+        match self.flip_policy {
+            FlipPolicy::Alternate => true,
+            FlipPolicy::Random(seed) => mix(seed ^ mix(interval as u64)) & 1 == 1,
+            FlipPolicy::Toward(wall) => interval == 0 && self.start_gravity == wall.invert(),
+        }
+    }
+
+    /// Whether dropping a disc in `column` would flip gravity, which only
+    /// happens on the last move of a round when it doesn't win outright (or
+    /// wins don't persist through flips), and when the flip policy moves
+    /// gravity that round.
+    pub fn move_causes_flip(&self, column: Column<W>) -> bool {
+        if !self.flips() || self.round + 1 != self.flip_interval {
+            return false;
+        }
+
+        self.flip_moves_gravity(self.intervals)
+            && matches!(self.with_move(column), Ok(next) if next.round == 0)
+    }
+
+    /// A flip or pop can complete lines for both colours at once, in which
+    /// case the game is a tie.
+    fn check_win_all(&self) -> Option<Winner> {
+        if Board::<W, H>::BITBOARD_FITS {
+            return bitboard::find_win(&self.state, self.win_length);
+        }
+
+        self.scan_win_all()
+    }
+
+    /// `check_win_all` by scanning out from every disc, for boards too big
+    /// for a bitboard.
+    fn scan_win_all(&self) -> Option<Winner> {
+        let mut winners = Column::all()
+            .flat_map(|c| (0..H as u8).map(move |h| (c, h)))
+            .filter_map(|(c, h)| self.check_win(c, h));
+
+        let first = winners.next()?;
+
+        if winners.any(|w| w != first) {
+            Some(Winner::Tie)
+        } else {
+            Some(first)
+        }
+    }
+
+    /// The cells of the line that won the game, preferring one running
+    /// through the last placed disc. Longer runs are cut down to the
+    /// `win_length` cells reaching forward from that disc.
+    #[cfg(feature = "alloc")]
+    pub fn winning_line(&self) -> Option<(Winner, Vec<(Column<W>, u8)>)> {
+        let winner = self.winner?;
+        let colour = winner.to_colour()?;
+
+        if let Some((column, row)) = self.last_move() {
+            if let Some(line) = self.line_through(column, row) {
+                return Some((winner, line));
+            }
+        }
+
+        // the win was made by a flip somewhere away from the last disc
+        for c in Column::all() {
+            for h in 0..H as u8 {
+                if self.state.piece_at(c, h) != Some(colour) {
+                    continue;
+                }
+
+                if let Some(line) = self.line_through(c, h) {
+                    return Some((winner, line));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The cell the disc dropped by the last move is in now, which a flip
+    /// since may have carried it across the board. `None` if the last move
+    /// wasn't a drop.
+    #[cfg(feature = "alloc")]
+    pub fn last_move(&self) -> Option<(Column<W>, u8)> {
+        let lane = match self.history.last()? {
+            Move::Drop(column) => column.to_idx(),
+            Move::Pop(_) | Move::Swap => return None,
+        };
+
+        let flipped =
+            self.flips() && self.round == 0 && self.flip_moves_gravity(self.intervals - 1);
+
+        let depth = if flipped {
+            // the disc was on top of its stack, the flip carried it to the
+            // wall gravity now points at
+            0
+        } else {
+            self.state.lane_height(lane) - 1
+        };
+
+        Some(self.state.lane_cell(lane, depth))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn line_through(&self, column: Column<W>, height: u8) -> Option<Vec<(Column<W>, u8)>> {
+        let colour = self.state.piece_at(column, height)?;
+        let reach = self.win_length as i8 - 1;
+
+        let cell = |dx: i8, dy: i8, depth: i8| {
+            Some((
+                column.offset(dx * depth)?,
+                row_offset::<H>(height, dy * depth)?,
+            ))
+        };
+
+        let extent = |dx: i8, dy: i8| {
+            (1..=reach)
+                .take_while(|&depth| match cell(dx, dy, depth) {
+                    Some((c, h)) => self.state.piece_at(c, h) == Some(colour),
+                    None => false,
+                })
+                .count() as i8
+        };
+
+        for &(dx, dy) in &[(1, 0), (0, 1), (1, 1), (1, -1)] {
+            let forward = extent(dx, dy);
+            let backward = extent(-dx, -dy);
+
+            if forward + backward < reach {
+                continue;
+            }
+
+            let start = forward.min(reach) - reach;
+            let line = (start..=start + reach)
+                .map(|depth| cell(dx, dy, depth).unwrap())
+                .collect();
+
+            return Some(line);
+        }
+
+        None
+    }
+
+    fn check_win(&self, column: Column<W>, height: u8) -> Option<Winner> {
+        let colour = self.state.piece_at(column, height)?;
+
+        const DIRECTIONS: &[(i8, i8, usize)] = &[
+            (-1, 1, 0),
+            (0, 1, 1),
+            (1, 1, 2),
+            (-1, 0, 3),
+            (1, 0, 3),
+            (-1, -1, 2),
+            (0, -1, 1),
+            (1, -1, 0),
+        ];
+
+        // 0:\ 1:| 2:/ 3:-
+        let mut count_in_direction = [1; 4];
+
+        let mut stopped_checking_direction = [false; 8];
+
+        for depth in 1..self.win_length as i8 {
+            for (i, &(dx, dy, dir_idx)) in DIRECTIONS.iter().enumerate() {
+                if stopped_checking_direction[i] {
+                    continue;
+                }
+
+                let check_col = match column.offset(dx * depth) {
+                    Some(c) => c,
+                    None => continue,
+                };
+                let check_row = match row_offset::<H>(height, dy * depth) {
+                    Some(c) => c,
+                    None => continue,
+                };
+
+                let colour_at_pos = self.state.piece_at(check_col, check_row);
+
+                if colour_at_pos != Some(colour) {
+                    stopped_checking_direction[i] = true;
+                } else {
+                    count_in_direction[dir_idx] += 1;
+                }
+            }
+        }
+
+        for &x in &count_in_direction {
+            if x >= self.win_length {
+                return Some(Winner::from_colour(colour));
+            }
+        }
+
+        None
+    }
+
+    /// The columns the current player may drop into, left to right.
+    #[cfg(feature = "alloc")]
+    pub fn legal_moves(&self) -> Vec<Column<W>> {
+        if self.is_finished() {
+            return Vec::new();
+        }
+
+        self.state.allowed_columns().into_iter().collect()
+    }
+
+    const MASK_FITS: () = assert!(W <= 8, "move masks only fit boards up to 8 columns wide");
+
+    /// The columns the current player may drop into as bits, column A being
+    /// the lowest, without allocating. Zero once the game is over.
+    ///
+    /// Only boards up to 8 columns wide have a mask, wider ones fail to
+    /// build.
+    pub fn legal_move_mask(&self) -> u8 {
+        let () = Self::MASK_FITS;
+
+        if self.is_finished() {
+            return 0;
+        }
+
+        self.state
+            .allowed_columns()
+            .into_iter()
+            .fold(0, |mask, column| mask | 1 << column.to_idx())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    /// A tie is either a full board, or a flip or pop that gave both
+    /// colours a line at once.
+    pub fn winner(&self) -> Option<Winner> {
+        self.winner
+    }
+
+    pub fn is_won_by(&self, colour: Colour) -> bool {
+        self.winner == Some(Winner::from_colour(colour))
+    }
+
+    pub fn is_tie(&self) -> bool {
+        self.winner == Some(Winner::Tie)
+    }
+
+    /// The colour that lost, `None` if the game is unfinished or tied.
+    pub fn loser(&self) -> Option<Colour> {
+        self.winner?.to_colour().map(Colour::invert)
+    }
+
+    pub fn current_colour(&self) -> Colour {
+        self.current_colour
+    }
+
+    /// The colour that moved first, or was to move first in a game started
+    /// from a position.
+    pub fn first_player(&self) -> Colour {
+        self.first_player
+    }
+
+    pub fn state(&self) -> &Board<W, H> {
+        &self.state
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn history(&self) -> &[Move<W>] {
+        &self.history
+    }
+
+    /// How many moves have been made, pops and swaps included.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// The move the current player is on, counting from 1 and going up
+    /// once both players have moved.
+    pub fn turn_number(&self) -> usize {
+        self.ply() / 2 + 1
+    }
+}
+
+impl<const W: usize, const H: usize> core::fmt::Display for Game<W, H> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "{}", self.state)?;
+
+        match self.winner {
+            None => write!(f, "{} to move", colour_name(self.current_colour)),
+            Some(Winner::Tie) => write!(f, "Tie"),
+            Some(w) => write!(f, "{}", w),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const W: usize, const H: usize> rubot::Game for Game<W, H> {
+    type Player = Colour;
+    type Action = Column<W>;
+    type Fitness = Fitness;
+    type Actions = OrderedColumnsIterator<W>;
+
+    fn actions(&self, player: Self::Player) -> (bool, Self::Actions) {
+        let actions = if self.is_finished() {
+            OrderedColumnsIterator {
+                allowed: AllowedColumnsIterator::new_empty(),
+                swaps: 0,
+            }
+        } else {
+            // a different order at every ply, so ties aren't always broken
+            // toward the same side
+            let swaps = match self.tie_break {
+                0 => 0,
+                seed => mix(seed ^ mix(self.ply() as u64)) as u32,
+            };
+
+            OrderedColumnsIterator {
+                swaps,
+                ..self.state.ordered_columns()
+            }
+        };
+
+        (player == self.current_colour(), actions)
+    }
+
+    fn execute(&mut self, action: &Self::Action, player: Self::Player) -> Self::Fitness {
+        self.make_move(*action).unwrap();
+        self.evaluate(player)
+    }
+
+    // only winning with the very next move can't be beaten, as any later
+    // win still loses to a sooner one, and the same goes for losing
+    fn is_upper_bound(&self, fitness: Self::Fitness, _player: Self::Player) -> bool {
+        fitness >= Fitness::win_at(self.ply() + 1)
+    }
+
+    fn is_lower_bound(&self, fitness: Self::Fitness, _player: Self::Player) -> bool {
+        fitness <= Fitness::loss_at(self.ply() + 1)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn board(notation: &str) -> ClassicBoard {
+        Board::from_notation(notation).unwrap()
+    }
+
+    /// A game without flips after dropping in each column named in `moves`.
+    fn play(moves: &str) -> Classic {
+        let mut game = Classic::new(Colour::Red, false);
+        for column in moves.chars() {
+            game.make_move(Column::try_from(column).unwrap()).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn games_show_who_won_or_is_to_move_by_name() {
+        let game = play("ABABABA");
+        assert!(game.to_string().ends_with("\nRed wins"));
+
+        let game = play("A");
+        assert!(game.to_string().ends_with("\nYellow to move"));
+    }
+
+    #[test]
+    fn filling_the_board_with_a_winning_disc_isnt_a_tie() {
+        // yellow's top row is only missing A, and nothing else lines up
+        let board = Board::<4, 4>::from_notation("RRY/RYRY/YRRY/RRYY/d").unwrap();
+
+        for flipping in [false, true] {
+            // the last disc ends a round, so a flip follows it
+            let mut game = Game::builder()
+                .first_player(Colour::Yellow)
+                .flipping(flipping)
+                .flip_interval(1)
+                .persist_wins_through_flip(false)
+                .position(board.clone())
+                .build();
+            assert_eq!(game.winner(), None);
+
+            game.make_move(Column::new(0).unwrap()).unwrap();
+
+            assert!(game.state().is_full());
+            assert_eq!(game.winner(), Some(Winner::Yellow));
+        }
+    }
+
+    #[test]
+    fn lines_broken_by_the_flip_after_them_only_win_if_wins_persist() {
+        // red's disc on top of A finishes the second row, but D is a disc taller
+        // than A to C, so the flip leaves D's red a row below the others
+        for persist in [true, false] {
+            let mut game = Game::builder()
+                .flipping(true)
+                .flip_interval(1)
+                .persist_wins_through_flip(persist)
+                .position(board("Y/RR/YR/YRY/-/-/-/d"))
+                .build();
+
+            game.make_move(Column::A).unwrap();
+
+            if persist {
+                assert_eq!(game.winner(), Some(Winner::Red));
+                assert_eq!(game.state().gravity(), Gravity::Down);
+            } else {
+                assert_eq!(game.winner(), None);
+                assert_eq!(game.state().gravity(), Gravity::Up);
+            }
+        }
+    }
+
+    #[test]
+    fn lines_made_by_a_flip_away_from_the_last_disc_win() {
+        // red's discs top out at different heights in A to D, so the flip
+        // after yellow's disc in G lines them up along the ceiling
+        let mut game = Game::builder()
+            .first_player(Colour::Yellow)
+            .flipping(true)
+            .flip_interval(1)
+            .position(board("R/YR/YYR/YR/-/-/-/d"))
+            .build();
+        assert_eq!(game.winner(), None);
+
+        game.make_move(Column::G).unwrap();
+
+        let (column, row) = game.last_move().unwrap();
+        assert_eq!((column, row), (Column::G, BOARD_HEIGHT as u8 - 1));
+        assert_eq!(game.check_win(column, row), None);
+        assert_eq!(game.check_win_all(), Some(Winner::Red));
+        assert_eq!(game.winner(), Some(Winner::Red));
+    }
+
+    #[test]
+    fn get_is_none_above_the_board() {
+        // row 6 of A would be row 0 of B if it were read straight through
+        let board = board("R/Y/-/-/-/-/-/d");
+
+        assert_eq!(board.get(Column::A, 0), Some(Colour::Red));
+        assert_eq!(board.get(Column::B, 0), Some(Colour::Yellow));
+        assert_eq!(board.get(Column::A, 1), None);
+        assert_eq!(board.get(Column::A, BOARD_HEIGHT as u8), None);
+        assert_eq!(board.get(Column::G, u8::MAX), None);
+    }
+
+    #[test]
+    fn legal_move_masks_have_a_bit_per_legal_move() {
+        // the last move fills column A and the one after wins
+        for game in [play(""), play("AAAAAA"), play("ABABABA")] {
+            let mask = game.legal_move_mask();
+            assert_eq!(mask.count_ones() as usize, game.legal_moves().len());
+
+            for column in game.legal_moves() {
+                assert_ne!(mask & 1 << column.to_idx(), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn physical_rows_follow_gravity() {
+        let top = BOARD_HEIGHT as u8 - 1;
+
+        let down = ClassicBoard::with_gravity(Gravity::Down);
+        assert_eq!(down.physical_row(0), 0);
+        assert_eq!(down.physical_row(top), top);
+
+        let up = ClassicBoard::with_gravity(Gravity::Up);
+        assert_eq!(up.physical_row(0), top);
+        assert_eq!(up.physical_row(top), 0);
+
+        for board in [down, up] {
+            for height in [0, top] {
+                let row = board.physical_row(height);
+                assert_eq!(board.logical_height(row), height);
+            }
+        }
+    }
+
+    #[test]
+    fn drops_land_on_the_physical_row_of_the_stack_height() {
+        let mut up = ClassicBoard::with_gravity(Gravity::Up);
+        for height in 0..BOARD_HEIGHT as u8 {
+            assert_eq!(up.drop_row(Column::A), Some(up.physical_row(height)));
+            up.place_on_column(Column::A, Colour::Red);
+        }
+        assert_eq!(up.drop_row(Column::A), None);
+    }
+
+    #[test]
+    fn undoing_across_flips_gives_back_every_position() {
+        let mut game = Classic::new(Colour::Red, true);
+        let moves = [3, 3, 2, 4, 4, 1, 0, 5, 6, 2, 3];
+        let mut positions = vec![game.clone()];
+
+        for &column in &moves {
+            game.make_move(Column::new(column).unwrap()).unwrap();
+            positions.push(game.clone());
+        }
+        // a flip every two moves, and the rounds didn't line up with the end
+        assert_eq!(game.state().gravity(), Gravity::Up);
+        assert_eq!(game.round, 1);
+
+        for (idx, &column) in moves.iter().enumerate().rev() {
+            assert_eq!(
+                game.undo_move(),
+                Ok(Move::Drop(Column::new(column).unwrap()))
+            );
+            assert_eq!(game, positions[idx], "after undoing move {}", idx);
+        }
+
+        assert_eq!(game.undo_move(), Err(UndoError::NoMoves));
+    }
+
+    #[test]
+    fn winning_line_gives_the_cells_of_each_direction() {
+        use Column as C;
+
+        let lines = [
+            ("AABBCCD", [(C::A, 0), (C::B, 0), (C::C, 0), (C::D, 0)]),
+            ("ABABABA", [(C::A, 0), (C::A, 1), (C::A, 2), (C::A, 3)]),
+            ("ABBCDCCDGDD", [(C::A, 0), (C::B, 1), (C::C, 2), (C::D, 3)]),
+            ("GFFEDEEDADD", [(C::D, 3), (C::E, 2), (C::F, 1), (C::G, 0)]),
+            // a run of five, cut down to the four reaching furthest forward
+            // from the last disc
+            ("AABBDDEEC", [(C::B, 0), (C::C, 0), (C::D, 0), (C::E, 0)]),
+        ];
+
+        for (moves, line) in lines {
+            let game = play(moves);
+            assert_eq!(
+                game.winning_line(),
+                Some((Winner::Red, line.to_vec())),
+                "{}",
+                moves
+            );
+        }
+    }
+
+    #[test]
+    fn flipping_and_winning_on_a_5x4_board() {
+        let mut game = Game::<5, 4>::new(Colour::Red, true);
+        let column = |c: char| Column::try_from(c).unwrap();
+
+        game.make_move(column('A')).unwrap();
+        game.make_move(column('A')).unwrap();
+        // the round ended, so the stack slid up against the top with yellow
+        // still above red
+        assert_eq!(game.state().to_notation(), "RY/-/-/-/-/u");
+
+        // red stacks E while flips carry the stack back and forth
+        for c in "EAEBEC".chars() {
+            game.make_move(column(c)).unwrap();
+        }
+        assert_eq!(game.winner(), None);
+        game.make_move(column('E')).unwrap();
+
+        assert_eq!(game.winner(), Some(Winner::Red));
+        assert_eq!(game.state().gravity(), Gravity::Down);
+        assert_eq!(
+            game.winning_line(),
+            Some((Winner::Red, (0..4).map(|row| (column('E'), row)).collect()))
+        );
+    }
+
+    #[test]
+    fn win_length_sets_how_long_a_line_wins() {
+        let moves = "ABABABA";
+
+        let mut three = Classic::with_win_length(Colour::Red, false, 3);
+        let mut five = Classic::with_win_length(Colour::Red, false, 5);
+
+        for (idx, column) in moves.chars().enumerate() {
+            let column = Column::try_from(column).unwrap();
+            five.make_move(column).unwrap();
+
+            // red's third disc in A, the fifth move
+            if idx < 5 {
+                assert_eq!(three.winner(), None);
+                three.make_move(column).unwrap();
+            }
+        }
+
+        assert_eq!(three.winner(), Some(Winner::Red));
+        assert_eq!(three.winning_line().unwrap().1.len(), 3);
+        // the same four in a row as wins a classic game
+        assert_eq!(five.state().longest_run(Colour::Red), 4);
+        assert_eq!(five.winner(), None);
+    }
+
+    #[test]
+    fn win_lengths_as_long_as_the_board_are_allowed() {
+        // only a full bottom row of red wins
+        let mut game = Classic::with_win_length(Colour::Red, false, BOARD_WIDTH);
+        for column in "AABBCCDDEEFF".chars() {
+            game.make_move(Column::try_from(column).unwrap()).unwrap();
+        }
+        assert_eq!(game.winner(), None);
+
+        game.make_move(Column::G).unwrap();
+        assert_eq!(game.winner(), Some(Winner::Red));
+    }
+
+    #[test]
+    #[should_panic(expected = "a line of 0 can't be made on a 7x6 board")]
+    fn win_lengths_of_zero_panic() {
+        Classic::builder().win_length(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "a line of 8 can't be made on a 7x6 board")]
+    fn win_lengths_longer_than_the_board_panic() {
+        Classic::builder().win_length(BOARD_WIDTH + 1);
+    }
+
+    #[test]
+    fn flipped_boards_leave_the_original_alone() {
+        let original = board("RY/Y/-/RRY/-/-/-/d");
+
+        let flipped = original.flipped();
+        assert_eq!(original, board("RY/Y/-/RRY/-/-/-/d"));
+
+        let mut flipped_in_place = original.clone();
+        flipped_in_place.flip_gravity();
+        assert_eq!(flipped, flipped_in_place);
+        assert_ne!(flipped, original);
+    }
+
+    #[test]
+    fn flipping_twice_gives_back_the_board() {
+        // a lone disc, a full column, and partly filled ones of both heights
+        for notation in [
+            "-/-/-/R/-/-/-/d",
+            "-/-/-/RYRYRY/-/-/-/d",
+            "R/YY/RYR/YRYRYR/RYRYR/-/Y/d",
+            "-/-/-/R/-/-/-/u",
+            "RY/-/-/RYRYRY/-/-/Y/u",
+        ] {
+            let original = board(notation);
+            let mut flipped = original.clone();
+
+            flipped.flip_gravity();
+            assert_ne!(flipped.gravity(), original.gravity());
+            flipped.flip_gravity();
+
+            assert_eq!(flipped, original, "{}", notation);
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn flipping_random_boards_twice_gives_them_back() {
+        use rand::prelude::*;
+
+        let mut rng = StdRng::seed_from_u64(41);
+
+        for _ in 0..1000 {
+            let original = ClassicBoard::random(&mut rng);
+            let mut flipped = original.clone();
+
+            flipped.flip_gravity();
+            flipped.flip_gravity();
+
+            assert_eq!(flipped, original, "\n{}", original);
+        }
+    }
+
+    #[test]
+    fn played_boards_pass_the_invariants_and_corrupted_ones_dont() {
+        let game = play("DDCEEBAFGCD");
+        assert_eq!(game.state().check_invariants(), Ok(()));
+
+        // a red disc floating two cells above the top of column A
+        let mut floating = game.state().clone();
+        let idx = ClassicBoard::index_of(Column::A, 3);
+        floating.present.set(idx, true);
+        floating.tiles.set(idx, true);
+        let err = floating.check_invariants().unwrap_err();
+        assert!(err.contains("column A row 3"), "{}", err);
+
+        // colour left behind in an empty cell
+        let mut stray = game.state().clone();
+        stray.tiles.set(ClassicBoard::index_of(Column::G, 5), true);
+        let err = stray.check_invariants().unwrap_err();
+        assert!(err.contains("coloured red without a disc"), "{}", err);
+    }
+
+    #[test]
+    fn games_come_back_whole_from_their_parts() {
+        let mut game = Classic::builder()
+            .first_player(Colour::Yellow)
+            .flipping(true)
+            .flip_interval(3)
+            .flip_policy(FlipPolicy::Random(7))
+            .win_length(5)
+            .persist_wins_through_flip(false)
+            .build();
+        for column in [Column::D, Column::C, Column::D, Column::E, Column::B] {
+            game.make_move(column).unwrap();
+        }
+
+        let mut rebuilt = Game::from_parts(game.clone().into_parts()).unwrap();
+        assert_eq!(rebuilt, game);
+
+        // it plays on and undoes the same, flips included
+        for column in [Column::A, Column::G, Column::F, Column::D] {
+            game.make_move(column).unwrap();
+            rebuilt.make_move(column).unwrap();
+            assert_eq!(rebuilt, game);
+        }
+        while game.undo_move().is_ok() {
+            rebuilt.undo_move().unwrap();
+            assert_eq!(rebuilt, game);
+        }
+    }
+
+    #[test]
+    fn parts_with_the_wrong_winner_are_rejected() {
+        let mut parts = play("DDDDCC").into_parts();
+        parts.winner = Some(Winner::Red);
+        assert_eq!(
+            Game::from_parts(parts).unwrap_err(),
+            PartsError::Winner(Some(Winner::Red), None)
+        );
+
+        let mut parts = play("ABABABA").into_parts();
+        parts.winner = None;
+        assert_eq!(
+            Game::from_parts(parts).unwrap_err(),
+            PartsError::Winner(None, Some(Winner::Red))
+        );
+    }
+
+    #[test]
+    fn parts_with_the_wrong_turn_or_round_are_rejected() {
+        let mut parts = play("DDC").into_parts();
+        parts.current_colour = Colour::Red;
+        assert_eq!(
+            Game::from_parts(parts).unwrap_err(),
+            PartsError::Turn(Colour::Red)
+        );
+
+        // three moves into two move rounds is one move into the second
+        let mut game = Classic::new(Colour::Red, true);
+        for column in [Column::D, Column::D, Column::C] {
+            game.make_move(column).unwrap();
+        }
+        let mut parts = game.into_parts();
+        assert_eq!(parts.round, 1);
+        parts.round = 0;
+        assert_eq!(Game::from_parts(parts).unwrap_err(), PartsError::Round(0));
+    }
+
+    #[test]
+    fn threat_maps_use_the_games_win_length() {
+        let mut game = Classic::with_win_length(Colour::Red, false, 3);
+        for column in [Column::A, Column::G, Column::B, Column::G] {
+            game.make_move(column).unwrap();
+        }
+
+        // C completes red's line of three, which a line of four wouldn't
+        let map = game.threat_map(Colour::Red);
+        let best = map.iter().flatten().max().unwrap();
+        assert_eq!(map[0][2], *best);
+        assert!(map[0][2] > game.state().threat_map(Colour::Red)[0][2]);
+    }
+
+    #[test]
+    fn popping_can_hand_the_opponent_a_win() {
+        let mut game = Classic::builder().pop_out(true).build();
+        for column in "AAEBGCGD".chars() {
+            game.make_move(Column::try_from(column).unwrap()).unwrap();
+        }
+        assert_eq!(
+            game.make_move_ex(Move::Pop(Column::B)),
+            Err(MoveError::NotYourPiece(Column::B))
+        );
+
+        // yellow's disc in A falls in beside B, C and D
+        game.make_move_ex(Move::Pop(Column::A)).unwrap();
+        assert_eq!(game.winner(), Some(Winner::Yellow));
+        assert_eq!(game.state().get(Column::A, 0), Some(Colour::Yellow));
+    }
+
+    #[test]
+    fn pops_need_pop_out_rules() {
+        let mut game = play("AAEBGCGD");
+
+        assert_eq!(
+            game.make_move_ex(Move::Pop(Column::A)),
+            Err(MoveError::CannotPop)
+        );
+        assert_eq!(game, play("AAEBGCGD"));
+
+        // red has won in A, which doesn't make a pop any more of a move
+        let mut over = play("ABABABA");
+        assert!(over.is_finished());
+        assert_eq!(
+            over.make_move_ex(Move::Pop(Column::A)),
+            Err(MoveError::CannotPop)
+        );
+    }
+
+    #[test]
+    fn blocked_cells_break_up_lines() {
+        let mut game = Classic::builder().block(Column::D, 3).unwrap().build();
+        for column in "DADADBD".chars() {
+            game.make_move(Column::try_from(column).unwrap()).unwrap();
+        }
+
+        // the fourth red disc settled above the blocked cell
+        assert_eq!(game.state().get(Column::D, 3), None);
+        assert_eq!(game.state().get(Column::D, 4), Some(Colour::Red));
+        assert_eq!(game.winner(), None);
+        assert_eq!(game.state().to_svg().matches("<rect").count(), 2);
+    }
+
+    #[test]
+    fn only_empty_cells_on_the_board_can_be_blocked() {
+        assert_eq!(
+            Classic::builder().block(Column::D, 6).unwrap_err(),
+            BlockError::OffBoard(Column::D, 6)
+        );
+        assert_eq!(
+            Classic::builder()
+                .position(board("R/-/-/-/-/-/-/d"))
+                .block(Column::A, 0)
+                .unwrap_err(),
+            BlockError::Occupied(Column::A, 0)
+        );
+    }
+
+    #[test]
+    fn jagged_boards_come_back_from_bytes_and_saves() {
+        let path = std::env::temp_dir().join(format!("c4-jagged-{}", std::process::id()));
+
+        // a pyramid, flipping every move so discs settle against the blocked
+        // cells at the top as well
+        let mut game = Classic::builder()
+            .column_heights([3, 4, 5, 6, 5, 4, 3])
+            .unwrap()
+            .flipping(true)
+            .flip_interval(1)
+            .build();
+        for column in [Column::A, Column::D, Column::A, Column::G, Column::B] {
+            game.make_move(column).unwrap();
+        }
+        assert_eq!(game.state().gravity(), Gravity::Up);
+
+        let bytes = game.state().to_bytes();
+        assert_eq!(ClassicBoard::from_bytes(&bytes).as_ref(), Ok(game.state()));
+
+        game.save(&path).unwrap();
+        let loaded = Classic::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, game);
+        assert!(loaded.state().is_blocked(Column::A, 3));
+        assert!(!loaded.state().is_blocked(Column::D, 5));
+    }
+
+    #[test]
+    fn legal_moves_leave_out_full_columns_and_finished_games() {
+        let game = play("AAAAAA");
+        assert!(game.state().column_full(Column::A));
+        assert_eq!(
+            game.legal_moves(),
+            Column::all().skip(1).collect::<Vec<_>>()
+        );
+
+        assert_eq!(play("ABABABA").legal_moves(), vec![]);
+    }
+
+    #[test]
+    fn boards_show_top_row_first_over_the_column_letters() {
+        let mut position = board("RY/Y/-/R/-/-/-/d");
+        position.block(Column::G, 0).unwrap();
+
+        assert_eq!(
+            position.to_string(),
+            "_______\n\
+             _______\n\
+             _______\n\
+             _______\n\
+             Y______\n\
+             RY_R__#\n\
+             ABCDEFG"
+        );
+        assert_eq!(
+            play("DC").to_string(),
+            format!("{}\nRed to move", board("-/-/Y/R/-/-/-/d"))
+        );
+    }
+
+    #[test]
+    fn columns_parse_from_a_letter_of_either_case() {
+        assert_eq!("a".parse::<Column<7>>(), Ok(Column::A));
+        assert_eq!("G".parse::<Column<7>>(), Ok(Column::G));
+        assert_eq!(
+            "h".parse::<Column<7>>(),
+            Err(ParseColumnError::Invalid("h".to_owned()))
+        );
+        assert_eq!("".parse::<Column<7>>(), Err(ParseColumnError::Empty));
+        assert_eq!(
+            "AB".parse::<Column<7>>(),
+            Err(ParseColumnError::Invalid("AB".to_owned()))
+        );
+
+        assert_eq!(Column::<7>::try_from('d'), Ok(Column::D));
+        assert!(Column::<7>::try_from('1').is_err());
+    }
+
+    #[test]
+    fn errors_read_as_sentences() {
+        let err: MoveError<7> = MoveError::ColumnFull(Column::G);
+        assert_eq!(err.to_string(), "column G is full");
+        assert_eq!(
+            MoveError::<7>::GameOver.to_string(),
+            "the game is already over"
+        );
+        assert_eq!(
+            MoveError::NotYourPiece(Column::<7>::B).to_string(),
+            "the disc at the bottom of column B isn't yours"
+        );
+        assert_eq!(UndoError::NoMoves.to_string(), "no moves have been made");
+
+        // and go through `?` into a boxed error
+        fn play_full() -> Result<(), Box<dyn std::error::Error>> {
+            play("AAAAAA").make_move(Column::A)?;
+            Ok(())
+        }
+        assert_eq!(play_full().unwrap_err().to_string(), "column A is full");
+    }
+
+    #[test]
+    fn with_move_leaves_the_game_alone() {
+        let game = play("DC");
+
+        let moved = game.with_move(Column::D).unwrap();
+        assert_eq!(game, play("DC"));
+
+        let mut made = game.clone();
+        made.make_move(Column::D).unwrap();
+        assert_eq!(moved, made);
+
+        assert_eq!(
+            play("AAAAAA").with_move(Column::A),
+            Err(MoveError::ColumnFull(Column::A))
+        );
+    }
+
+    #[test]
+    fn drop_rows_follow_gravity() {
+        let down = board("RYRYR/-/Y/-/-/-/RYRYRY/d");
+        assert_eq!(down.drop_row(Column::A), Some(5));
+        assert_eq!(down.drop_row(Column::B), Some(0));
+        assert_eq!(down.drop_row(Column::C), Some(1));
+        assert_eq!(down.drop_row(Column::G), None);
+
+        let up = board("RYRYR/-/Y/-/-/-/RYRYRY/u");
+        assert_eq!(up.drop_row(Column::A), Some(0));
+        assert_eq!(up.drop_row(Column::B), Some(5));
+        assert_eq!(up.drop_row(Column::C), Some(4));
+        assert_eq!(up.drop_row(Column::G), None);
+
+        // and match where the disc lands
+        for board in [down, up] {
+            for column in Column::all() {
+                if let Some(row) = board.drop_row(column) {
+                    let mut dropped = board.clone();
+                    dropped.place_on_column(column, Colour::Red);
+                    assert_eq!(dropped.piece_at(column, row), Some(Colour::Red));
+                    assert_eq!(board.piece_at(column, row), None);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mirrored_boards_share_a_canonical_key() {
+        let position = board("RY/-/R/-/-/Y/-/d");
+        let mirrored = position.mirror();
+        assert_eq!(mirrored, board("-/Y/-/-/R/-/RY/d"));
+        assert_eq!(mirrored.mirror(), position);
+
+        assert_eq!(position.canonical_key(), mirrored.canonical_key());
+        assert_ne!(
+            position.canonical_key(),
+            board("RY/-/R/-/-/-/Y/d").canonical_key()
+        );
+
+        // sideways gravity swaps sides with the columns
+        assert_eq!(
+            board("R...../-/-/-/-/-/-/l").mirror(),
+            board("-/-/-/-/-/-/R...../r")
+        );
+    }
+
+    #[test]
+    fn flips_come_every_flip_interval_moves() {
+        let moves = [
+            Column::A,
+            Column::B,
+            Column::C,
+            Column::E,
+            Column::F,
+            Column::G,
+            Column::D,
+        ];
+
+        for (flipping, interval, flips_after) in [
+            (true, 1, &[1, 2, 3, 4, 5, 6, 7][..]),
+            (true, 3, &[3, 6][..]),
+            (false, 1, &[][..]),
+        ] {
+            let mut game = Classic::builder()
+                .flipping(flipping)
+                .flip_interval(interval)
+                .build();
+            let mut gravity = Gravity::Down;
+
+            for (idx, &column) in moves.iter().enumerate() {
+                game.make_move(column).unwrap();
+
+                if flips_after.contains(&(idx + 1)) {
+                    gravity = gravity.invert();
+                }
+                assert_eq!(
+                    game.state().gravity(),
+                    gravity,
+                    "move {} with interval {}",
+                    idx + 1,
+                    interval
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn left_gravity_stacks_discs_against_column_a() {
+        // under sideways gravity each "column" names the row a disc slides
+        // along
+        let mut game = Classic::builder().gravity(Gravity::Left).build();
+        for column in [Column::C, Column::C, Column::C, Column::A] {
+            game.make_move(column).unwrap();
+        }
+
+        let state = game.state();
+        assert_eq!(state.piece_at(Column::A, 2), Some(Colour::Red));
+        assert_eq!(state.piece_at(Column::B, 2), Some(Colour::Yellow));
+        assert_eq!(state.piece_at(Column::C, 2), Some(Colour::Red));
+        assert_eq!(state.piece_at(Column::A, 0), Some(Colour::Yellow));
+        assert_eq!(state.drop_cell(Column::C), Some((Column::D, 2)));
+        assert_eq!(state.to_notation(), "Y.R.../..Y.../..R.../-/-/-/-/l");
+
+        // there are only as many rows as the board is tall
+        assert!(!game.legal_moves().contains(&Column::G));
+        assert_eq!(game.legal_moves().len(), 6);
+    }
+
+    #[test]
+    fn iter_cells_goes_up_each_column_in_turn() {
+        let game = play("DDCE");
+        let cells = game.state().iter_cells().collect::<Vec<_>>();
+
+        assert_eq!(cells.len(), BOARD_WIDTH * BOARD_HEIGHT);
+        assert_eq!(cells[0], (Column::A, 0, None));
+        assert_eq!(cells[1], (Column::A, 1, None));
+        assert_eq!(cells[BOARD_HEIGHT], (Column::B, 0, None));
+        assert_eq!(
+            cells[3 * BOARD_HEIGHT + 1],
+            (Column::D, 1, Some(Colour::Yellow))
+        );
+
+        for (column, row, disc) in cells.iter().copied() {
+            assert_eq!(disc, game.state().piece_at(column, row));
+        }
+        assert_eq!(cells.iter().filter(|(_, _, d)| d.is_some()).count(), 4);
+    }
+
+    #[test]
+    fn column_offsets_off_the_board_are_none() {
+        assert_eq!(Column::A.offset(-1), None);
+        assert_eq!(Column::G.offset(1), None);
+        assert_eq!(Column::A.offset(i8::MIN), None);
+        assert_eq!(Column::D.offset(-3), Some(Column::A));
+        assert_eq!(Column::D.offset(3), Some(Column::G));
+
+        // wider than a u8 column could wrap round to
+        let wide = Column::<300>::new(299).unwrap();
+        assert_eq!(wide.offset(1), None);
+        assert_eq!(Column::<300>::new(0).unwrap().offset(-1), None);
+        assert_eq!(wide.offset(-100), Column::new(199));
+    }
+
+    #[test]
+    fn games_can_start_from_a_puzzle() {
+        // yellow to move, and win along the second row with D
+        let mut game = Game::builder()
+            .first_player(Colour::Yellow)
+            .flipping(true)
+            .flip_interval(2)
+            .position(board("RY/YY/RY/R/R/-/-/d"))
+            .build();
+
+        assert_eq!(game.winner(), None);
+        assert_eq!(game.winning_moves(), vec![Column::D]);
+        assert_eq!(game.undo_move(), Err(UndoError::NoMoves));
+
+        // the first flip comes a whole round in
+        game.make_move(Column::G).unwrap();
+        assert_eq!(game.state().gravity(), Gravity::Down);
+        game.make_move(Column::G).unwrap();
+        assert_eq!(game.state().gravity(), Gravity::Up);
+
+        // and finished positions start finished
+        let won = Game::builder()
+            .position(board("RRRR/YYY/-/-/-/-/-/d"))
+            .build();
+        assert_eq!(won.winner(), Some(Winner::Red));
+    }
+
+    #[test]
+    fn winners_come_from_optional_colours() {
+        assert_eq!(Winner::from(Some(Colour::Red)), Winner::Red);
+        assert_eq!(Winner::from(Some(Colour::Yellow)), Winner::Yellow);
+        assert_eq!(Winner::from(None), Winner::Tie);
+
+        assert_eq!(Colour::all(), &[Colour::Red, Colour::Yellow]);
+    }
+
+    #[test]
+    fn ordered_columns_go_out_from_the_centre() {
+        let order = |board: &ClassicBoard| board.ordered_columns().into_iter().collect::<Vec<_>>();
+
+        assert_eq!(
+            order(&ClassicBoard::new()),
+            [
+                Column::D,
+                Column::C,
+                Column::E,
+                Column::B,
+                Column::F,
+                Column::A,
+                Column::G
+            ]
+        );
+        assert_eq!(
+            order(&board("-/-/RYRYRY/-/-/-/-/d")),
+            [
+                Column::D,
+                Column::E,
+                Column::B,
+                Column::F,
+                Column::A,
+                Column::G
+            ]
+        );
+
+        // the two middle columns of an even board come left first
+        let even = Board::<6, 4>::new()
+            .ordered_columns()
+            .into_iter()
+            .map(|c| c.to_idx())
+            .collect::<Vec<_>>();
+        assert_eq!(even, [2, 3, 1, 4, 0, 5]);
+    }
+
+    #[test]
+    fn playable_columns_keep_up_with_moves_flips_and_undos() {
+        let recomputed = |board: &ClassicBoard| {
+            Column::all()
+                .filter(|&c| !board.column_full(c))
+                .collect::<Vec<_>>()
+        };
+
+        let mut game = Classic::builder().flipping(true).flip_interval(1).build();
+        let mut positions = vec![game.clone()];
+        for column in "AAAAAABGGGGGGC".chars() {
+            game.make_move(Column::try_from(column).unwrap()).unwrap();
+            positions.push(game.clone());
+
+            let cached = game
+                .state()
+                .allowed_columns()
+                .into_iter()
+                .collect::<Vec<_>>();
+            assert_eq!(cached, recomputed(game.state()), "after {}", column);
+        }
+        assert_eq!(game.legal_moves().len(), 5);
+
+        while game.undo_move().is_ok() {
+            positions.pop();
+            assert_eq!(&game, positions.last().unwrap());
+
+            let cached = game
+                .state()
+                .allowed_columns()
+                .into_iter()
+                .collect::<Vec<_>>();
+            assert_eq!(cached, recomputed(game.state()));
+        }
+    }
+
+    #[test]
+    fn column_stacks_read_from_the_bottom_up() {
+        use Colour::{Red as R, Yellow as Y};
+
+        let mut position = board("-/-/-/RRY/-/-/-/d");
+        assert_eq!(position.column_stack(Column::D), vec![R, R, Y]);
+        assert_eq!(position.column_stack(Column::A), vec![]);
+
+        // once flipped the discs slide up without changing order, so the
+        // stack still reads the same
+        position.flip_gravity();
+        assert_eq!(position.piece_at(Column::D, 5), Some(Y));
+        assert_eq!(position.column_stack(Column::D), vec![R, R, Y]);
+
+        let position = board("-/-/-/RRY/-/-/-/u");
+        assert_eq!(position.piece_at(Column::D, 3), Some(R));
+        assert_eq!(position.column_stack(Column::D), vec![R, R, Y]);
+    }
+
+    #[test]
+    fn flips_lining_up_both_colours_are_a_tie() {
+        // A to D are topped with red over yellow at different heights, so
+        // the flip lines up the reds along the ceiling and the yellows under
+        // them
+        let mut game = Game::builder()
+            .flipping(true)
+            .flip_interval(1)
+            .position(board("YR/RYR/YR/RYR/-/Y/Y/d"))
+            .build();
+        assert_eq!(game.winner(), None);
+
+        game.make_move(Column::E).unwrap();
+
+        assert_eq!(game.state().gravity(), Gravity::Up);
+        assert_eq!(game.winner(), Some(Winner::Tie));
+    }
+
+    #[test]
+    fn ply_and_turn_number_count_the_moves() {
+        let game = Classic::new(Colour::Red, false);
+        assert_eq!((game.ply(), game.turn_number()), (0, 1));
+
+        let mut game = play("DDC");
+        assert_eq!((game.ply(), game.turn_number()), (3, 2));
+
+        game.make_move(Column::E).unwrap();
+        assert_eq!((game.ply(), game.turn_number()), (4, 3));
+
+        game.undo_move().unwrap();
+        assert_eq!((game.ply(), game.turn_number()), (3, 2));
+
+        // discs in a starting position aren't moves
+        let game = Game::builder().position(board("RY/-/-/-/-/-/-/d")).build();
+        assert_eq!(game.ply(), 0);
+    }
+
+    #[test]
+    fn resigning_hands_the_opponent_the_win() {
+        let mut game = Classic::new(Colour::Red, false);
+        game.resign(Colour::Red).unwrap();
+        assert_eq!(game.winner(), Some(Winner::Yellow));
+
+        assert_eq!(game.resign(Colour::Yellow), Err(MoveError::GameOver));
+        assert_eq!(game.agree_draw(), Err(MoveError::GameOver));
+        assert_eq!(game.winner(), Some(Winner::Yellow));
+
+        let mut game = play("ABABABA");
+        assert_eq!(game.resign(Colour::Red), Err(MoveError::GameOver));
+        assert_eq!(game.winner(), Some(Winner::Red));
+    }
+
+    #[test]
+    fn agreed_draws_are_ties() {
+        let mut game = play("DD");
+        game.agree_draw().unwrap();
+        assert_eq!(game.winner(), Some(Winner::Tie));
+        assert_eq!(game.make_move(Column::D), Err(MoveError::GameOver));
+    }
+
+    #[test]
+    fn only_the_last_move_of_a_round_causes_a_flip() {
+        let mut game = Classic::builder().flipping(true).flip_interval(3).build();
+
+        for column in [Column::A, Column::B] {
+            assert!(!game.move_causes_flip(Column::D));
+            game.make_move(column).unwrap();
+        }
+        // round == flip_interval - 1
+        assert!(game.move_causes_flip(Column::D));
+        game.make_move(Column::D).unwrap();
+        assert_eq!(game.state().gravity(), Gravity::Up);
+        assert!(!game.move_causes_flip(Column::D));
+
+        assert!(!Classic::new(Colour::Red, false).move_causes_flip(Column::D));
+
+        // a winning move ends the game before the flip
+        let game = Game::builder()
+            .flipping(true)
+            .flip_interval(1)
+            .position(board("RRR/YYY/-/-/-/-/-/d"))
+            .build();
+        assert!(!game.move_causes_flip(Column::A));
+        assert!(game.move_causes_flip(Column::D));
+    }
+
+    #[test]
+    fn flip_policies_choose_the_gravity_after_each_round() {
+        let gravities = |policy| {
+            let mut game = Classic::builder()
+                .flipping(true)
+                .flip_interval(1)
+                .flip_policy(policy)
+                .build();
+
+            "AAAAAABBBBBB"
+                .chars()
+                .map(|column| {
+                    game.make_move(Column::try_from(column).unwrap()).unwrap();
+                    game.state().gravity()
+                })
+                .collect::<Vec<_>>()
+        };
+        let (down, up) = (Gravity::Down, Gravity::Up);
+
+        let alternate = gravities(FlipPolicy::Alternate);
+        assert_eq!(alternate, [up, down].repeat(6));
+
+        let random = gravities(FlipPolicy::Random(67));
+        assert_eq!(random, gravities(FlipPolicy::Random(67)));
+        assert!(random.contains(&up) && random.contains(&down));
+        assert!(random.windows(2).any(|w| w[0] == w[1]));
+
+        assert_eq!(gravities(FlipPolicy::Toward(Gravity::Up)), vec![up; 12]);
+        assert_eq!(gravities(FlipPolicy::Toward(Gravity::Down)), vec![down; 12]);
+    }
+
+    #[test]
+    fn outcomes_can_be_asked_about_directly() {
+        let won = play("ABABABA");
+        assert!(won.is_won_by(Colour::Red));
+        assert!(!won.is_won_by(Colour::Yellow));
+        assert!(!won.is_tie());
+        assert_eq!(won.loser(), Some(Colour::Yellow));
+
+        let mut tied = play("DD");
+        tied.agree_draw().unwrap();
+        assert!(!tied.is_won_by(Colour::Red) && !tied.is_won_by(Colour::Yellow));
+        assert!(tied.is_tie());
+        assert_eq!(tied.loser(), None);
+
+        let unfinished = play("DD");
+        assert!(!unfinished.is_won_by(Colour::Red) && !unfinished.is_won_by(Colour::Yellow));
+        assert!(!unfinished.is_tie());
+        assert_eq!(unfinished.loser(), None);
+    }
+
+    #[test]
+    fn validate_move_refuses_full_columns_and_finished_games() {
+        let game = play("AAAAAA");
+        assert_eq!(
+            game.validate_move(Column::A),
+            Err(MoveError::ColumnFull(Column::A))
+        );
+        assert_eq!(game.validate_move(Column::B), Ok(()));
+
+        let game = play("ABABABA");
+        assert_eq!(game.validate_move(Column::C), Err(MoveError::GameOver));
+    }
+
+    #[test]
+    fn swapping_keeps_the_disc_and_the_colour_to_move() {
+        let mut game = Classic::builder().swap_rule(true).build();
+        assert!(!game.can_swap());
+        assert_eq!(game.make_move_ex(Move::Swap), Err(MoveError::CannotSwap));
+
+        game.make_move(Column::D).unwrap();
+        assert!(game.can_swap());
+        game.make_move_ex(Move::Swap).unwrap();
+
+        // the second player now owns red's disc, and yellow is still to move,
+        // played by whoever moved first
+        assert!(game.swapped());
+        assert_eq!(game.current_colour(), Colour::Yellow);
+        assert_eq!(game.first_player(), Colour::Red);
+        assert_eq!(game.state().get(Column::D, 0), Some(Colour::Red));
+        assert_eq!(game.ply(), 2);
+
+        assert!(!game.can_swap());
+        assert_eq!(game.make_move_ex(Move::Swap), Err(MoveError::CannotSwap));
+        game.make_move(Column::C).unwrap();
+        assert_eq!(game.current_colour(), Colour::Red);
+
+        game.undo_move().unwrap();
+        assert_eq!(game.undo_move(), Ok(Move::Swap));
+        assert!(!game.swapped());
+        assert!(game.can_swap());
+    }
+
+    #[test]
+    fn swaps_need_the_swap_rule() {
+        let mut game = play("D");
+        assert!(!game.can_swap());
+        assert_eq!(game.make_move_ex(Move::Swap), Err(MoveError::CannotSwap));
+    }
+
+    #[test]
+    fn layout_keys_ignore_gravity() {
+        // a full column sits the same way whichever way gravity pulls it
+        let down = board("RYRYRY/-/-/-/-/-/-/d");
+        let up = board("RYRYRY/-/-/-/-/-/-/u");
+        assert_eq!(down.layout_key(), up.layout_key());
+        assert_ne!(down.key(), up.key());
+        assert_ne!(down.canonical_key(), up.canonical_key());
+
+        let other = board("RYRYRR/-/-/-/-/-/-/d");
+        assert_ne!(down.layout_key(), other.layout_key());
+    }
+
+    #[test]
+    fn position_at_rebuilds_earlier_boards() {
+        // with flips, so the boards in between aren't just fewer discs
+        let mut game = Classic::new(Colour::Red, true);
+        let mut boards = vec![game.state().clone()];
+        for column in "DDCEBFA".chars() {
+            game.make_move(Column::try_from(column).unwrap()).unwrap();
+            boards.push(game.state().clone());
+        }
+
+        for (ply, board) in boards.iter().enumerate() {
+            let position = game.position_at(ply).unwrap();
+            assert_eq!(position.state(), board, "ply {}", ply);
+            assert_eq!(position.ply(), ply);
+        }
+        assert_eq!(
+            game.position_at(3).unwrap().current_colour(),
+            Colour::Yellow
+        );
+        assert_eq!(game.position_at(boards.len()), None);
+    }
+
+    #[test]
+    fn undoing_a_move_keeps_a_line_an_earlier_flip_made() {
+        // the flip after yellow's G lines red up along the ceiling, as in
+        // `lines_made_by_a_flip_away_from_the_last_disc_win`
+        let mut game = Game::builder()
+            .first_player(Colour::Yellow)
+            .flipping(true)
+            .flip_interval(1)
+            .position(board("R/YR/YYR/YR/-/-/-/d"))
+            .build();
+        game.make_move(Column::G).unwrap();
+        assert_eq!(game.winner(), Some(Winner::Red));
+
+        // a game saved with a move after that line, which undoing leaves on
+        // the board
+        let mut parts = game.clone().into_parts();
+        parts.board.place_on_column(Column::F, Colour::Red);
+        parts.history.push(Move::Drop(Column::F));
+        parts.current_colour = Colour::Yellow;
+        parts.round = 1;
+        let mut loaded = Game::from_parts(parts).unwrap();
+
+        assert_eq!(loaded.undo_move(), Ok(Move::Drop(Column::F)));
+        assert_eq!(loaded.state().gravity(), Gravity::Up);
+        assert_eq!(loaded.winner(), Some(Winner::Red));
+
+        // while undoing the flip itself takes the line away again
+        game.undo_move().unwrap();
+        assert_eq!(game.state().gravity(), Gravity::Down);
+        assert_eq!(game.winner(), None);
+    }
+
+    #[test]
+    fn longest_runs_count_any_direction() {
+        // red climbs diagonally from A, over yellow's pairs in B and C
+        let position = board("R/YR/YYR/-/-/-/-/d");
+        assert_eq!(position.longest_run(Colour::Red), 3);
+        assert_eq!(position.longest_run(Colour::Yellow), 2);
+
+        let position = board("RRR/-/-/-/-/-/-/d");
+        assert_eq!(position.longest_run(Colour::Red), 3);
+    }
+
+    #[test]
+    fn scattered_discs_are_runs_of_one() {
+        let position = board("Y/-/Y/-/Y/-/R/d");
+        assert_eq!(position.longest_run(Colour::Yellow), 1);
+        assert_eq!(position.longest_run(Colour::Red), 1);
+        assert_eq!(board("-/-/-/-/-/-/-/d").longest_run(Colour::Red), 0);
+    }
+
+    #[test]
+    fn last_move_follows_the_disc_through_flips() {
+        let mut game = play("DD");
+        assert_eq!(game.last_move(), Some((Column::D, 1)));
+        game.undo_move().unwrap();
+        assert_eq!(game.last_move(), Some((Column::D, 0)));
+        game.undo_move().unwrap();
+        assert_eq!(game.last_move(), None);
+
+        // every move flips, so red's disc ends up against the ceiling, and
+        // yellow's, dropped up under it, is carried back down to the floor
+        let mut game = Classic::builder().flipping(true).flip_interval(1).build();
+        game.make_move(Column::A).unwrap();
+        assert_eq!(game.state().gravity(), Gravity::Up);
+        assert_eq!(game.last_move(), Some((Column::A, BOARD_HEIGHT as u8 - 1)));
+
+        game.make_move(Column::A).unwrap();
+        assert_eq!(game.state().gravity(), Gravity::Down);
+        assert_eq!(game.last_move(), Some((Column::A, 0)));
+        assert_eq!(game.state().get(Column::A, 0), Some(Colour::Yellow));
+
+        game.undo_move().unwrap();
+        assert_eq!(game.last_move(), Some((Column::A, BOARD_HEIGHT as u8 - 1)));
+    }
+
+    #[test]
+    fn rotating_twice_gives_the_board_back() {
+        let mut blocked = board("RY/Y/-/R/-/-/-/d");
+        blocked.block(Column::G, 0).unwrap();
+
+        for position in [
+            board("RY/Y/-/R/-/-/-/d"),
+            board("Y.R.../..Y.../..R.../-/-/-/-/l"),
+            blocked,
+        ] {
+            let rotated = position.rotate_180();
+            assert_eq!(rotated.gravity(), position.gravity().invert());
+            assert_eq!(rotated.rotate_180(), position);
+        }
+
+        let rotated = board("RY/-/-/-/-/-/-/d").rotate_180();
+        assert_eq!(
+            rotated.get(Column::G, BOARD_HEIGHT as u8 - 1),
+            Some(Colour::Red)
+        );
+        assert_eq!(
+            rotated.get(Column::G, BOARD_HEIGHT as u8 - 2),
+            Some(Colour::Yellow)
+        );
+    }
+
+    #[test]
+    fn rotated_boards_have_the_same_winner() {
+        let winner =
+            |position: ClassicBoard| Classic::builder().position(position).build().winner();
+
+        for (notation, expected) in [
+            ("R/YR/YYR/YYYR/-/-/-/d", Some(Winner::Red)),
+            ("YR/YR/YR/R/-/-/-/d", None),
+        ] {
+            let position = board(notation);
+            assert_eq!(winner(position.clone()), expected, "{}", notation);
+            assert_eq!(winner(position.rotate_180()), expected, "{}", notation);
+        }
+    }
+}
@@ -1,590 +1,1052 @@
-#![feature(type_alias_impl_trait)]
-
-use std::convert::TryInto;
 use std::time::Duration;
 
-use bitvec::prelude::*;
-use dialoguer;
-use num_enum::{IntoPrimitive, TryFromPrimitive};
-use rubot;
-
-const BOARD_HEIGHT: usize = 6;
-const BOARD_WIDTH: usize = 7;
+use c4::{
+    write_move, AnalysisCache, Bot, BotConfig, BotTurn, Classic, Colour, Column, Difficulty,
+    Driver, Game, Match, MatchSide, NetError, NetGame, PlayerKind, Tablebase, Winner, BOARD_HEIGHT,
+    BOARD_WIDTH,
+};
+use clap::{ArgEnum, Parser, Subcommand};
+
+const HINT_TIME: Duration = Duration::from_secs(1);
+// positions each side of a tournament remembers between games
+const CACHE_SIZE: usize = 100_000;
+
+// exit codes with `--format`, so scripts can tell how the game ended. Bad
+// arguments exit with `EXIT_ERROR` too, rather than clap's 2
+const EXIT_TIE: i32 = 0;
+const EXIT_RED: i32 = 1;
+const EXIT_YELLOW: i32 = 2;
+const EXIT_ERROR: i32 = 3;
+const EXIT_UNFINISHED: i32 = 4;
+
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum PlayAs {
+    Red,
+    Yellow,
+    Bot,
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum Colour {
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum Side {
     Red,
     Yellow,
 }
 
-impl Colour {
-    fn invert(self) -> Self {
-        match self {
-            Colour::Red => Colour::Yellow,
-            Colour::Yellow => Colour::Red,
-        }
-    }
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum Output {
+    Json,
+    Plain,
+}
 
-    fn to_bool(self) -> bool {
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum Level {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Level {
+    fn difficulty(self) -> Difficulty {
         match self {
-            Colour::Red => true,
-            Colour::Yellow => false,
+            Level::Easy => Difficulty::Easy,
+            Level::Medium => Difficulty::Medium,
+            Level::Hard => Difficulty::Hard,
         }
     }
+}
 
-    fn from_bool(v: bool) -> Colour {
-        if v {
-            Colour::Red
-        } else {
-            Colour::Yellow
+impl Side {
+    fn colour(self) -> Colour {
+        match self {
+            Side::Red => Colour::Red,
+            Side::Yellow => Colour::Yellow,
         }
     }
 }
 
-impl std::fmt::Display for Colour {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let col = match self {
-            Colour::Red => "R",
-            Colour::Yellow => "Y",
-        };
+/// Connect four with optional gravity flipping. Run without arguments to
+/// be prompted for every option instead.
+#[derive(Debug, Parser)]
+struct Args {
+    /// The side played by a human, or `bot` to let two bots play. Without
+    /// it only the given moves are played.
+    #[clap(long, arg_enum)]
+    play_as: Option<PlayAs>,
 
-        write!(f, "{}", col)
-    }
-}
+    #[clap(long, arg_enum, default_value = "red")]
+    first: Side,
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum Winner {
-    Red,
-    Yellow,
-    Tie,
-}
+    /// Bot think time in seconds
+    #[clap(long, default_value = "5")]
+    think_time: u64,
 
-impl Winner {
-    fn from_colour(colour: Colour) -> Self {
-        match colour {
-            Colour::Red => Winner::Red,
-            Colour::Yellow => Winner::Yellow,
-        }
-    }
+    #[clap(long, arg_enum, default_value = "hard")]
+    difficulty: Level,
 
-    fn to_colour(self) -> Option<Colour> {
-        match self {
-            Winner::Red => Some(Colour::Red),
-            Winner::Yellow => Some(Colour::Yellow),
-            _ => None,
-        }
-    }
-}
+    /// Flip gravity every two rounds
+    #[clap(long)]
+    flipping: bool,
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
-#[repr(u8)]
-pub enum Column {
-    A = 0,
-    B,
-    C,
-    D,
-    E,
-    F,
-    G,
-}
-
-impl std::fmt::Display for Column {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let col = match self {
-            Column::A => "A",
-            Column::B => "B",
-            Column::C => "C",
-            Column::D => "D",
-            Column::E => "E",
-            Column::F => "F",
-            Column::G => "G",
-        };
+    /// Let the second player swap sides instead of answering the first move
+    #[clap(long)]
+    swap_rule: bool,
 
-        write!(f, "{}", col)
-    }
-}
+    /// Comma separated columns to play before anyone else moves, eg. `D,D,C`
+    #[clap(long)]
+    moves: Option<String>,
 
-impl Column {
-    fn to_idx(self) -> usize {
-        u8::from(self) as usize
-    }
+    /// Host a network game on this address, eg. `0.0.0.0:4040`, playing red
+    #[clap(long, conflicts_with = "join")]
+    host: Option<String>,
 
-    fn offset(self, offset: i8) -> Option<Column> {
-        let v = u8::from(self) as i16 + offset as i16;
-        (v as u8).try_into().ok()
-    }
+    /// Join the network game hosted at this address, playing yellow
+    #[clap(long)]
+    join: Option<String>,
 
-    // fn succ(self) -> Option<Column> {
-    //     self.offset(1)
-    // }
-
-    // fn pred(self) -> Option<Column> {
-    //     self.offset(-1)
-    // }
-
-    fn all() -> &'static [Column] {
-        const ALL: &'static [Column] = &[
-            Column::A,
-            Column::B,
-            Column::C,
-            Column::D,
-            Column::E,
-            Column::F,
-            Column::G,
-        ];
+    /// Milliseconds to wait between two bot moves, to watch bots play
+    #[clap(long, default_value = "0")]
+    delay: u64,
 
-        ALL
-    }
-}
+    /// Clear the screen before showing each move
+    #[clap(long)]
+    clear: bool,
+
+    /// Let the bot search during the human's turn, for the hard difficulty
+    #[clap(long)]
+    ponder: bool,
+
+    /// Read columns from stdin, separated by spaces or new lines, printing
+    /// the game after each one, until the input or the game ends
+    #[clap(long, conflicts_with = "play-as")]
+    stdin: bool,
 
-// const fn max(a: usize, b: usize) -> usize {
-//     if a < b {
-//         b
-//     } else {
-//         a
-//     }
-// }
+    /// Print the result in this format instead of the final board, and exit
+    /// with 0 for a tie, 1 if red won, 2 if yellow won, 3 on an error or 4
+    /// if the game isn't over
+    #[clap(long, arg_enum)]
+    format: Option<Output>,
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct Board {
-    // eventually: heights: [u8; max(BOARD_HEIGHT, BOARD_WIDTH)],
-    heights: [u8; BOARD_WIDTH],
-    present: bitarr![for BOARD_HEIGHT * BOARD_WIDTH],
-    tiles: bitarr![for BOARD_HEIGHT * BOARD_WIDTH],
-    gravity_down: bool,
+    #[clap(subcommand)]
+    command: Option<Command>,
 }
 
-struct AllowedColumnsIterator {
-    allowed: bitarr![for BOARD_WIDTH],
+#[derive(Debug, Subcommand)]
+enum Command {
+    Tournament(Tournament),
+    Selfplay(Selfplay),
+    Match(Series),
+    Tablebase(Solve),
 }
 
-impl AllowedColumnsIterator {
-    fn from_board(board: &Board) -> Self {
-        let mut allowed = bitarr![0; BOARD_WIDTH];
+#[derive(Debug, Clone, Copy, ArgEnum)]
+enum Format {
+    Jsonl,
+    Csv,
+}
 
-        for col in Column::all() {
-            if !board.column_full(*col) {
-                allowed.set(col.to_idx(), true);
-            }
-        }
+/// Solves every position of a 5x4 board, writing them to `output` for
+/// `Tablebase::load`
+#[derive(Debug, clap::Args)]
+struct Solve {
+    output: String,
 
-        Self { allowed }
-    }
+    #[clap(long, default_value = "4")]
+    win_length: usize,
 
-    fn new_empty() -> Self {
-        Self {
-            allowed: Default::default(),
-        }
-    }
+    #[clap(long)]
+    first_yellow: bool,
 }
 
-impl IntoIterator for AllowedColumnsIterator {
-    type Item = Column;
+/// Plays a bot against itself, writing every position to stdout as a
+/// training record
+#[derive(Debug, clap::Args)]
+struct Selfplay {
+    #[clap(long, default_value = "1")]
+    games: u32,
 
-    type IntoIter = impl Iterator<Item = Column>;
+    /// Bot think time in milliseconds
+    #[clap(long, default_value = "100")]
+    think_ms: u64,
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.allowed.into_iter().enumerate().filter_map(|(idx, c)| {
-            if c {
-                Some((idx as u8).try_into().unwrap())
-            } else {
-                None
-            }
-        })
-    }
+    #[clap(long, arg_enum, default_value = "hard")]
+    difficulty: Level,
+
+    #[clap(long)]
+    flipping: bool,
+
+    #[clap(long, arg_enum, default_value = "jsonl")]
+    format: Format,
 }
 
-impl Board {
-    fn new() -> Self {
-        Self {
-            heights: Default::default(),
-            present: Default::default(),
-            tiles: Default::default(),
-            gravity_down: true,
-        }
-    }
+/// Plays two bots against each other, swapping colours every game
+#[derive(Debug, clap::Args)]
+struct Tournament {
+    #[clap(long, default_value = "10")]
+    games: u32,
 
-    fn column_height(&self, column: Column) -> u8 {
-        self.heights[column.to_idx()]
-    }
+    /// The side played by the first bot in the first game
+    #[clap(long, arg_enum, default_value = "red")]
+    first: Side,
 
-    fn column_full(&self, column: Column) -> bool {
-        self.column_height(column) >= BOARD_HEIGHT as u8
-    }
+    /// First bot think time in milliseconds
+    #[clap(long, default_value = "100")]
+    a_think_ms: u64,
 
-    fn index_of(column: Column, height: u8) -> usize {
-        column.to_idx() * BOARD_HEIGHT + height as usize
-    }
+    #[clap(long, arg_enum, default_value = "hard")]
+    a_difficulty: Level,
 
-    fn place_on_column(&mut self, column: Column, colour: Colour) {
-        let height = self.column_height(column);
-        let height = if self.gravity_down {
-            height
-        } else {
-            BOARD_HEIGHT as u8 - (height + 1)
-        };
+    /// Second bot think time in milliseconds
+    #[clap(long, default_value = "100")]
+    b_think_ms: u64,
 
-        let idx = Board::index_of(column, height);
-        self.tiles.set(idx, colour.to_bool());
-        self.present.set(idx, true);
-        self.heights[column.to_idx()] += 1;
-    }
+    #[clap(long, arg_enum, default_value = "hard")]
+    b_difficulty: Level,
 
-    fn piece_at(&self, column: Column, height: u8) -> Option<Colour> {
-        let idx = Board::index_of(column, height);
-        if self.present[idx] {
-            Some(Colour::from_bool(self.tiles[idx]))
-        } else {
-            None
-        }
-    }
+    #[clap(long)]
+    flipping: bool,
 
-    fn allowed_columns(&self) -> AllowedColumnsIterator {
-        AllowedColumnsIterator::from_board(self)
-    }
+    /// Print the moves of every game
+    #[clap(long)]
+    show_moves: bool,
 
-    fn render(&self) {
-        for i in (0..BOARD_HEIGHT).rev() {
-            for &col in Column::all() {
-                match self.piece_at(col, i as u8) {
-                    Some(p) => print!("{}", p),
-                    None => print!("_"),
-                };
-            }
+    /// Seed the bots so the tournament can be replayed, which only plays
+    /// out the same way for difficulties that ignore the think time
+    #[clap(long)]
+    seed: Option<u64>,
+}
 
-            println!("");
-        }
+/// Plays two bots against each other, swapping colours every game, until
+/// one has won most of a best of `games`
+#[derive(Debug, clap::Args)]
+struct Series {
+    /// How many games the match is the best of
+    #[clap(long, default_value = "5")]
+    games: u32,
 
-        for c in Column::all() {
-            print!("{}", c);
-        }
+    /// First bot think time in milliseconds
+    #[clap(long, default_value = "100")]
+    a_think_ms: u64,
 
-        println!("");
-    }
+    #[clap(long, arg_enum, default_value = "hard")]
+    a_difficulty: Level,
+
+    /// Second bot think time in milliseconds
+    #[clap(long, default_value = "100")]
+    b_think_ms: u64,
+
+    #[clap(long, arg_enum, default_value = "hard")]
+    b_difficulty: Level,
+
+    #[clap(long)]
+    flipping: bool,
+
+    /// Seed the bots so the match can be replayed, see `tournament --seed`
+    #[clap(long)]
+    seed: Option<u64>,
 }
 
-fn row_offset(row: u8, offset: i8) -> Option<u8> {
-    let v = row as i16 + offset as i16;
-    let h = BOARD_HEIGHT as i16;
-    if v < 0 || v >= h {
-        None
-    } else {
-        Some(v as u8)
-    }
+#[derive(Debug, Default, Clone, Copy)]
+struct Tally {
+    wins: u32,
+    losses: u32,
+    ties: u32,
 }
 
-#[derive(Debug, Clone)]
-enum MoveError {
-    GameOver,
-    ColumnFull(Column),
+impl Tally {
+    fn record(&mut self, colour: Colour, winner: Option<Colour>) {
+        match winner {
+            Some(winner) if winner == colour => self.wins += 1,
+            Some(_) => self.losses += 1,
+            None => self.ties += 1,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-struct Game {
-    state: Board,
-    current_colour: Colour,
-    winner: Option<Winner>,
+struct Settings {
+    human_player: Option<Colour>,
+    first_player: Colour,
+    think_time: u64,
+    difficulty: Difficulty,
     flipping: bool,
-    round: u8,
+    swap_rule: bool,
+    typed_input: bool,
+    delay: Duration,
+    clear: bool,
+    ponder: bool,
 }
 
-impl Game {
-    fn new(starting_colour: Colour, flipping: bool) -> Self {
+impl Settings {
+    /// The settings given on the command line, where the interactive ones
+    /// come from `prompt_settings`.
+    fn from_args(args: &Args) -> Self {
         Self {
-            state: Board::new(),
-            current_colour: starting_colour,
-            winner: None,
-            flipping,
-            round: 0,
+            human_player: match args.play_as {
+                Some(PlayAs::Red) => Some(Colour::Red),
+                Some(PlayAs::Yellow) => Some(Colour::Yellow),
+                _ => None,
+            },
+            first_player: args.first.colour(),
+            think_time: args.think_time,
+            difficulty: args.difficulty.difficulty(),
+            flipping: args.flipping,
+            swap_rule: args.swap_rule,
+            typed_input: false,
+            delay: Duration::from_millis(args.delay),
+            clear: args.clear,
+            ponder: args.ponder,
         }
     }
 
-    fn make_move(&mut self, column: Column) -> Result<(), MoveError> {
-        if self.is_finished() {
-            return Err(MoveError::GameOver);
-        }
-
-        if self.state.column_full(column) {
-            return Err(MoveError::ColumnFull(column));
-        }
+    fn new_game(&self) -> Classic {
+        Classic::builder()
+            .first_player(self.first_player)
+            .flipping(self.flipping)
+            .swap_rule(self.swap_rule)
+            .build()
+    }
+}
 
-        self.state.place_on_column(column, self.current_colour);
+fn prompt_settings() -> Settings {
+    let colours = Colour::all();
+    let player_opts = &[Some(Colour::Red), Some(Colour::Yellow), None];
 
-        self.current_colour = self.current_colour.invert();
+    let human_player = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Play as")
+        .items(&["Red", "Yellow", "Bot v Bot"])
+        .interact()
+        .unwrap();
+    let human_player = player_opts[human_player];
 
-        let height = self.state.column_height(column) - 1;
+    let first_player = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Who goes first")
+        .items(&["Red", "Yellow"])
+        .interact()
+        .unwrap();
 
-        if let Some(winner) = self.check_win(column, height) {
-            self.winner = Some(winner);
-        }
+    let think_time: u64 = dialoguer::Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Bot think time")
+        .default(5)
+        .interact()
+        .unwrap();
 
-        if self.winner.is_some() {
-            return Ok(());
-        }
+    let difficulties = &[Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+    let difficulty = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Bot difficulty")
+        .items(&["Easy", "Medium", "Hard"])
+        .default(2)
+        .interact()
+        .unwrap();
 
-        self.round += 1;
+    let flipping = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Gravity change")
+        .items(&["Never", "Every two rounds"])
+        .interact()
+        .unwrap();
+    let flipping = flipping == 1;
 
-        if self.round == 2 && self.flipping {
-            self.round = 0;
-            self.flip()
-        }
+    let swap_rule = dialoguer::Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt("Let the second player swap sides after the first move")
+        .default(false)
+        .interact()
+        .unwrap();
 
-        if let Some(winner) = self.check_win_all() {
-            self.winner = Some(winner);
-        }
+    let typed_input = human_player.is_some()
+        && dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Column input")
+            .items(&["Pick from a list", "Type a letter"])
+            .interact()
+            .unwrap()
+            == 1;
+
+    let delay: u64 = if human_player.is_none() {
+        dialoguer::Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Delay between moves (ms)")
+            .default(0)
+            .interact()
+            .unwrap()
+    } else {
+        0
+    };
 
-        Ok(())
+    let clear = delay > 0
+        && dialoguer::Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Clear the screen between moves")
+            .default(true)
+            .interact()
+            .unwrap();
+
+    let ponder = human_player.is_some()
+        && difficulties[difficulty] == Difficulty::Hard
+        && dialoguer::Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Let the bot think during your turn")
+            .default(false)
+            .interact()
+            .unwrap();
+
+    Settings {
+        human_player,
+        first_player: colours[first_player],
+        think_time,
+        difficulty: difficulties[difficulty],
+        flipping,
+        swap_rule,
+        typed_input,
+        delay: Duration::from_millis(delay),
+        clear,
+        ponder,
     }
+}
 
-    fn flip(&mut self) {
-        for &column in Column::all() {
-            let idx = Board::index_of(column, 0);
-
-            if self.state.column_height(column) == 0 {
+/// Asks the human for their move, giving `None` if they resign, which is
+/// only offered when `resignable`.
+fn prompt_move(game: &Classic, typed_input: bool, resignable: bool) -> Option<Column<BOARD_WIDTH>> {
+    if typed_input {
+        loop {
+            let input: String =
+                dialoguer::Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                    .with_prompt("Your turn")
+                    .validate_with(|s: &String| {
+                        if s.eq_ignore_ascii_case("history")
+                            || (resignable && s.eq_ignore_ascii_case("resign"))
+                        {
+                            return Ok(());
+                        }
+
+                        match s.parse::<Column<BOARD_WIDTH>>() {
+                            Ok(c) => game.validate_move(c).map_err(|e| e.to_string()),
+                            Err(e) => Err(e.to_string()),
+                        }
+                    })
+                    .interact()
+                    .unwrap();
+
+            if input.eq_ignore_ascii_case("history") {
+                browse_history(game);
+                game.render_colored();
                 continue;
             }
 
-            let shift = BOARD_HEIGHT - self.state.column_height(column) as usize;
+            return input.parse().ok();
+        }
+    } else {
+        // every column is listed, full or not, so each keeps its place
+        let columns = Column::<BOARD_WIDTH>::all().collect::<Vec<_>>();
+        let mut items = columns
+            .iter()
+            .map(|&c| match game.validate_move(c) {
+                Ok(()) => c.to_string(),
+                Err(_) => format!("{} (full)", c),
+            })
+            .collect::<Vec<_>>();
+        items.push("Hint".to_owned());
+        items.push("History".to_owned());
+        if resignable {
+            items.push("Resign".to_owned());
+        }
+
+        loop {
+            let chosen = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Your turn")
+                .items(&items)
+                .interact()
+                .unwrap();
 
-            let present = &mut self.state.present[idx..idx + BOARD_HEIGHT];
-            let tiles = &mut self.state.tiles[idx..idx + BOARD_HEIGHT];
+            if let Some(&column) = columns.get(chosen) {
+                match game.validate_move(column) {
+                    Ok(()) => return Some(column),
+                    Err(e) => {
+                        println!("{}", e);
+                        continue;
+                    }
+                }
+            }
 
-            // println!("tiles before {:?} {} {}", present, column, shift);
+            if chosen == columns.len() + 1 {
+                browse_history(game);
+                game.render_colored();
+                continue;
+            }
 
-            if self.state.gravity_down {
-                // going up
-                present.shift_right(shift);
-                tiles.shift_right(shift);
-            } else {
-                // going down
-                present.shift_left(shift);
-                tiles.shift_left(shift);
+            if chosen > columns.len() {
+                return None;
             }
 
-            // println!("tiles after {:?}", present);
+            if let Some(hint) = game.suggest(HINT_TIME) {
+                let fitness = game
+                    .with_move(hint)
+                    .unwrap()
+                    .evaluate(game.current_colour());
+                println!("Try {} ({})", hint, fitness);
+            }
         }
+    }
+}
 
-        self.state.gravity_down = !self.state.gravity_down;
+/// Steps back and forward through the positions `game` went through, only
+/// showing them, until the human returns to the game.
+fn browse_history(game: &Classic) {
+    let mut ply = game.ply();
+
+    loop {
+        let position = game.position_at(ply).unwrap();
+        println!("After move {} of {}:", ply, game.ply());
+        position.render_colored();
+
+        let chosen = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("History")
+            .items(&["Back", "Forward", "Return to game"])
+            .default(0)
+            .interact()
+            .unwrap();
+
+        match chosen {
+            0 => ply = ply.saturating_sub(1),
+            1 => ply = (ply + 1).min(game.ply()),
+            _ => return,
+        }
     }
+}
 
-    fn check_win_all(&self) -> Option<Winner> {
-        for &c in Column::all() {
-            for h in 0..BOARD_HEIGHT {
-                if let Some(win) = self.check_win(c, h as u8) {
-                    return Some(win);
-                }
-            }
+fn play(settings: &Settings, game: Classic) -> Classic {
+    let bot = PlayerKind::Bot(BotConfig {
+        ponder: settings.ponder && settings.difficulty == Difficulty::Hard,
+        ..BotConfig::new(
+            settings.difficulty,
+            Duration::from_secs(settings.think_time),
+        )
+    });
+    let player = |colour| {
+        if settings.human_player == Some(colour) {
+            PlayerKind::Human
+        } else {
+            bot
         }
+    };
 
-        None
-    }
+    let mut driver = Driver::new(game, player(Colour::Red), player(Colour::Yellow));
 
-    fn check_win(&self, column: Column, height: u8) -> Option<Winner> {
-        let colour = match self.state.piece_at(column, height) {
-            Some(c) => c,
-            None => return None,
-        };
+    // only pause between two bot moves, never while a human is thinking
+    let mut bot_moved = false;
+    // whether the last move flipped gravity, pointed out above the next board
+    let mut flipped = false;
 
-        const DIRECTIONS: &[(i8, i8, usize)] = &[
-            (-1, 1, 0),
-            (0, 1, 1),
-            (1, 1, 2),
-            (-1, 0, 3),
-            (1, 0, 3),
-            (-1, -1, 2),
-            (0, -1, 1),
-            (1, -1, 0),
-        ];
+    while !driver.game().is_finished() {
+        if settings.clear {
+            print!("\x1b[2J\x1b[H");
+        }
 
-        // 0:\ 1:| 2:/ 3:-
-        let mut count_in_direction = [1; 4];
+        println!("Game State:");
+        if flipped {
+            println!("Gravity flipped!");
+        }
+        driver.game().render_colored();
+
+        if driver.is_human_turn() {
+            if driver.game().can_swap()
+                && dialoguer::Confirm::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                    .with_prompt("Swap sides, taking the first move as yours")
+                    .default(false)
+                    .interact()
+                    .unwrap()
+            {
+                driver.swap_sides().unwrap();
+                flipped = false;
+                continue;
+            }
 
-        let mut stopped_checking_direction = [false; 8];
+            match prompt_move(driver.game(), settings.typed_input, true) {
+                Some(column) => {
+                    let flips = driver.game().move_causes_flip(column);
+
+                    // the prompt only offers legal moves, but ask again
+                    // rather than crash if one slips through
+                    if let Err(e) = driver.play_human(column) {
+                        println!("{}", e);
+                        continue;
+                    }
+                    flipped = flips;
+                }
+                None => driver.resign().unwrap(),
+            }
+            bot_moved = false;
+        } else {
+            if bot_moved {
+                std::thread::sleep(settings.delay);
+            }
 
-        for depth in 1..=4 {
-            for (i, &(dx, dy, dir_idx)) in DIRECTIONS.into_iter().enumerate() {
-                if stopped_checking_direction[i] {
+            println!("Bot's Turn");
+            let before = driver.game().clone();
+            bot_moved = true;
+
+            let turn = match driver.play_bot() {
+                Ok(turn) => turn.expect("it's the bot's turn in an unfinished game"),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(EXIT_ERROR);
+                }
+            };
+            let stats = match turn {
+                BotTurn::Swapped => {
+                    println!("Bot swapped sides");
+                    flipped = false;
+                    continue;
+                }
+                BotTurn::Played(stats) => stats,
+                BotTurn::OutOfTime => {
+                    println!("Bot ran out of time");
+                    continue;
+                }
+                BotTurn::NoMove => {
+                    println!("Bot found no move and resigns");
+                    driver.resign().unwrap();
                     continue;
                 }
+            };
 
-                let check_col = match column.offset(dx * depth) {
-                    Some(c) => c,
-                    None => continue,
-                };
-                let check_row = match row_offset(height, dy * depth) {
-                    Some(c) => c,
-                    None => continue,
-                };
-
-                let colour_at_pos = self.state.piece_at(check_col, check_row);
-
-                if colour_at_pos != Some(colour) {
-                    stopped_checking_direction[i] = true;
-                } else {
-                    count_in_direction[dir_idx] += 1;
+            if settings.difficulty == Difficulty::Hard {
+                if let Some((line, fitness)) = c4::expected_line(&before, stats.chosen) {
+                    let line = line.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+                    println!(
+                        "Bot plays {} expecting {} ({})",
+                        stats.chosen,
+                        line.join(","),
+                        fitness
+                    );
                 }
             }
+            println!("Bot chose {}", stats);
+
+            flipped = before.move_causes_flip(stats.chosen);
         }
+    }
 
-        for &x in &count_in_direction {
-            if x >= 4 {
-                return Some(Winner::from_colour(colour));
+    driver.into_game()
+}
+
+fn apply_moves(game: &mut Classic, moves: &str) -> Result<(), String> {
+    let columns = moves
+        .split(',')
+        .enumerate()
+        .map(|(idx, mv)| {
+            mv.parse::<Column<BOARD_WIDTH>>()
+                .map_err(|e| format!("move {} is invalid: {}", idx, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    game.apply_moves(&columns)
+        .map_err(|(idx, e)| format!("move {} ({}) is illegal: {}", idx, columns[idx], e))
+}
+
+/// Plays the columns read from stdin, printing the game after each one.
+/// Anything that isn't a legal move is reported and skipped.
+fn play_stdin(game: &mut Classic) {
+    use std::io::BufRead;
+
+    for line in std::io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(EXIT_ERROR);
             }
-        }
+        };
 
-        // check if the board is full
-        for &col in Column::all() {
-            if !self.state.column_full(col) {
-                return None;
+        for token in line.split_whitespace() {
+            let column = match token.parse::<Column<BOARD_WIDTH>>() {
+                Ok(column) => column,
+                Err(e) => {
+                    println!("{}", e);
+                    continue;
+                }
+            };
+
+            if let Err(e) = game.validate_move(column) {
+                println!("{}", e);
+                continue;
             }
-        }
 
-        Some(Winner::Tie)
-    }
+            game.make_move(column).unwrap();
+            println!("{}", game);
 
-    fn is_finished(&self) -> bool {
-        self.winner.is_some()
+            if game.is_finished() {
+                return;
+            }
+        }
     }
+}
 
-    fn winner(&self) -> Option<Winner> {
-        self.winner
-    }
+fn play_network(mut net: NetGame) {
+    while !net.game().is_finished() {
+        println!("Game State:");
+        net.game().render_colored();
 
-    fn current_colour(&self) -> Colour {
-        self.current_colour
-    }
+        let result = if net.game().current_colour() == net.colour() {
+            // the protocol has no way to resign
+            let column = prompt_move(net.game(), false, false).unwrap();
+            net.play(column)
+        } else {
+            println!("Waiting for the other player");
+            net.wait_for_move().map(|_| ())
+        };
 
-    fn state(&self) -> &Board {
-        &self.state
+        match result {
+            Ok(()) => (),
+            // a refused move can be retried, and a disconnect ends the game
+            Err(e @ NetError::Rejected(_)) | Err(e @ NetError::Disconnected) => println!("{}", e),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(EXIT_ERROR);
+            }
+        }
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-enum Fitness {
-    Loss = 0,
-    Tie,
-    Win,
+    println!("{}", net.game());
 }
 
-impl rubot::Game for Game {
-    type Player = Colour;
-    type Action = Column;
-    type Fitness = Fitness;
-    type Actions = AllowedColumnsIterator;
-
-    fn actions(&self, player: Self::Player) -> (bool, Self::Actions) {
-        let actions = if self.is_finished() {
-            AllowedColumnsIterator::new_empty()
+fn tournament(args: &Tournament) {
+    let configs = [
+        (args.a_difficulty, Duration::from_millis(args.a_think_ms)),
+        (args.b_difficulty, Duration::from_millis(args.b_think_ms)),
+    ];
+    let mut by_config = [Tally::default(); 2];
+    let mut by_colour = [Tally::default(); 2];
+    // kept across games so openings the bots have already met are answered
+    // straight away
+    let caches = [
+        AnalysisCache::new(CACHE_SIZE),
+        AnalysisCache::new(CACHE_SIZE),
+    ];
+
+    for round in 0..args.games {
+        // red always moves first, so swapping colours swaps who starts
+        let a_colour = if round % 2 == 0 {
+            args.first.colour()
         } else {
-            self.state.allowed_columns()
+            args.first.colour().invert()
         };
+        let mut bots = match args.seed {
+            Some(seed) => {
+                // a different seed for every bot in every game
+                let seed = seed.wrapping_add(2 * round as u64);
+                [
+                    Bot::with_seed(a_colour, configs[0].1, seed),
+                    Bot::with_seed(a_colour.invert(), configs[1].1, seed.wrapping_add(1)),
+                ]
+            }
+            None => [
+                Bot::new(a_colour, configs[0].1),
+                Bot::new(a_colour.invert(), configs[1].1),
+            ],
+        };
+        for (bot, cache) in bots.iter_mut().zip(&caches) {
+            bot.use_cache(cache.clone());
+        }
+
+        let mut game = Classic::new(Colour::Red, args.flipping);
+
+        while !game.is_finished() {
+            let idx = if game.current_colour() == a_colour {
+                0
+            } else {
+                1
+            };
+            let column = bots[idx]
+                .select_move(&game, configs[idx].0.difficulty())
+                .unwrap();
+            game.make_move(column).unwrap();
+        }
 
-        (player == self.current_colour(), actions)
+        let winner = game.winner().and_then(|w| w.to_colour());
+
+        by_config[0].record(a_colour, winner);
+        by_config[1].record(a_colour.invert(), winner);
+        for (tally, &colour) in by_colour.iter_mut().zip(Colour::all()) {
+            tally.record(colour, winner);
+        }
+
+        if args.show_moves {
+            let moves = game
+                .history()
+                .iter()
+                .map(|&mv| write_move(mv))
+                .collect::<Vec<_>>();
+            let result = winner.map_or("tie".to_owned(), |w| format!("{} wins", w));
+
+            println!(
+                "game {}: A is {}, {} ({})",
+                round + 1,
+                a_colour,
+                moves.join(","),
+                result
+            );
+        }
     }
 
-    fn execute(&mut self, action: &Self::Action, player: Self::Player) -> Self::Fitness {
-        self.make_move(*action).unwrap();
+    println!("{:<24} {:>6} {:>6} {:>6}", "", "wins", "losses", "ties");
+
+    let rows = [
+        format!("A ({:?}, {}ms)", args.a_difficulty, args.a_think_ms),
+        format!("B ({:?}, {}ms)", args.b_difficulty, args.b_think_ms),
+        "Red".to_owned(),
+        "Yellow".to_owned(),
+    ];
+
+    for (name, tally) in rows.iter().zip(by_config.iter().chain(&by_colour)) {
+        println!(
+            "{:<24} {:>6} {:>6} {:>6}",
+            name, tally.wins, tally.losses, tally.ties
+        );
+    }
+}
 
-        match self.winner().and_then(Winner::to_colour) {
-            None => Fitness::Tie,
-            Some(c) if c == player => Fitness::Win,
-            _ => Fitness::Loss,
+fn series(args: &Series) {
+    let config = |level: Level, think_ms: u64, seed: u64| {
+        let mut config = BotConfig::new(level.difficulty(), Duration::from_millis(think_ms));
+        config.seed = args.seed.map(|s| s.wrapping_add(seed));
+        PlayerKind::Bot(config)
+    };
+    let mut series = Match::new(
+        config(args.a_difficulty, args.a_think_ms, 0),
+        config(args.b_difficulty, args.b_think_ms, 1),
+        args.games,
+    );
+
+    while let Some(mut driver) = series.next_game(Classic::new(Colour::Red, args.flipping)) {
+        let a_colour = series.colour(MatchSide::A);
+        if let Err(e) = driver.play_bots() {
+            eprintln!("game {}: {}", series.results().len() + 1, e);
+            std::process::exit(EXIT_ERROR);
         }
+
+        let result = match series.record(driver.game()) {
+            Ok(Some(MatchSide::A)) => "A wins",
+            Ok(Some(MatchSide::B)) => "B wins",
+            Ok(None) => "tie",
+            Err(e) => {
+                eprintln!("game {}: {}", series.results().len() + 1, e);
+                std::process::exit(EXIT_ERROR);
+            }
+        };
+        println!(
+            "game {}: A is {}, {} ({} - {}, {} tied)",
+            series.results().len(),
+            a_colour,
+            result,
+            series.wins(MatchSide::A),
+            series.wins(MatchSide::B),
+            series.ties()
+        );
     }
 
-    fn is_upper_bound(&self, fitness: Self::Fitness, _player: Self::Player) -> bool {
-        fitness == Fitness::Win
+    match series.winner() {
+        Some(MatchSide::A) => println!("A wins the match"),
+        Some(MatchSide::B) => println!("B wins the match"),
+        None => println!("the match is drawn"),
     }
+}
 
-    fn is_lower_bound(&self, fitness: Self::Fitness, _player: Self::Player) -> bool {
-        fitness == Fitness::Loss
+fn solve(args: &Solve) {
+    let first = if args.first_yellow {
+        Colour::Yellow
+    } else {
+        Colour::Red
+    };
+    if !(1..=5).contains(&args.win_length) {
+        eprintln!("a line of {} can't be made on a 5x4 board", args.win_length);
+        std::process::exit(EXIT_ERROR);
+    }
+    let game = Game::<5, 4>::with_win_length(first, false, args.win_length);
+
+    let tablebase = Tablebase::generate(&game);
+    let value = tablebase.lookup(&game).unwrap();
+    println!(
+        "solved {} positions, the first player gets a {:?} in {} moves",
+        tablebase.len(),
+        value.outcome,
+        value.distance
+    );
+
+    if let Err(e) = tablebase.save(&args.output) {
+        eprintln!("couldn't write {}: {}", args.output, e);
+        std::process::exit(1);
     }
 }
 
-fn perform() {
-    let colours = &[Colour::Red, Colour::Yellow];
-    let player_opts = &[Some(Colour::Red), Some(Colour::Yellow), None];
+fn selfplay(args: &Selfplay) {
+    let think_time = Duration::from_millis(args.think_ms);
+    let difficulty = args.difficulty.difficulty();
+    let mut header = matches!(args.format, Format::Csv);
 
-    let human_player = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt("Play as")
-        .items(&["Red", "Yellow", "Bot v Bot"])
-        .interact()
-        .unwrap();
-    let human_player = player_opts[human_player];
+    for _ in 0..args.games {
+        let mut bots = [
+            Bot::new(Colour::Red, think_time),
+            Bot::new(Colour::Yellow, think_time),
+        ];
+        let mut game = Classic::new(Colour::Red, args.flipping);
+        let mut moves = Vec::new();
+
+        while !game.is_finished() {
+            let bot = &mut bots[(game.current_colour() == Colour::Yellow) as usize];
+            let column = bot.select_move(&game, difficulty).unwrap();
+            game.make_move(column).unwrap();
+            moves.push(column);
+        }
 
-    let first_player = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt("Who goes first")
-        .items(&["Red", "Yellow"])
-        .interact()
-        .unwrap();
+        let records =
+            c4::Record::from_replay::<BOARD_HEIGHT>(&moves, Colour::Red, args.flipping).unwrap();
 
-    let think_time: u64 = dialoguer::Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt("Bot think time")
-        .default(5)
-        .interact()
-        .unwrap();
+        for record in records {
+            if header {
+                println!("{}", record.csv_header());
+                header = false;
+            }
 
-    let flipping = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
-        .with_prompt("Gravity change")
-        .items(&["Never", "Every two rounds"])
-        .interact()
-        .unwrap();
-    let flipping = flipping == 1;
+            match args.format {
+                Format::Jsonl => println!("{}", record.to_json()),
+                Format::Csv => println!("{}", record.to_csv()),
+            }
+        }
+    }
+}
 
-    let mut game = Game::new(colours[first_player], flipping);
+fn run(args: Args) {
+    match &args.command {
+        Some(Command::Tournament(t)) => return tournament(t),
+        Some(Command::Selfplay(s)) => return selfplay(s),
+        Some(Command::Match(m)) => return series(m),
+        Some(Command::Tablebase(t)) => return solve(t),
+        None => (),
+    }
 
-    let (red_bot, yellow_bot) = match human_player {
-        Some(Colour::Red) => (false, true),
-        Some(Colour::Yellow) => (true, false),
-        None => (true, true),
-    };
+    let settings = Settings::from_args(&args);
 
-    let mut red_bot = if red_bot {
-        Some(rubot::Bot::new(Colour::Red))
+    let net = if let Some(addr) = &args.host {
+        std::net::TcpListener::bind(addr)
+            .map_err(NetError::Io)
+            .and_then(|l| c4::host(&l, settings.first_player, settings.flipping))
+            .map(Some)
+    } else if let Some(addr) = &args.join {
+        c4::join(addr.as_str()).map(Some)
     } else {
-        None
+        Ok(None)
     };
 
-    let mut yellow_bot = if yellow_bot {
-        Some(rubot::Bot::new(Colour::Yellow))
-    } else {
-        None
+    match net {
+        Ok(Some(net)) => return play_network(net),
+        Ok(None) => (),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(EXIT_ERROR);
+        }
+    }
+
+    let mut game = settings.new_game();
+
+    if let Some(moves) = &args.moves {
+        if let Err(e) = apply_moves(&mut game, moves) {
+            eprintln!("{}", e);
+            std::process::exit(EXIT_ERROR);
+        }
+    }
+
+    if args.stdin {
+        play_stdin(&mut game);
+    } else if args.play_as.is_some() {
+        game = play(&settings, game);
+    }
+
+    let format = match args.format {
+        Some(format) => format,
+        None => return println!("{}", game),
     };
 
-    while !game.is_finished() {
-        println!("Game State:");
-        game.state().render();
+    println!("{}", summary(&game, format));
 
-        if Some(game.current_colour()) == human_player {
-            let items = game
-                .state()
-                .allowed_columns()
-                .into_iter()
-                .collect::<Vec<_>>();
-            let chosen = dialoguer::Select::with_theme(&dialoguer::theme::ColorfulTheme::default())
-                .with_prompt("Your turn")
-                .items(&items)
-                .interact()
-                .unwrap();
+    let code = match game.winner() {
+        Some(Winner::Red) => EXIT_RED,
+        Some(Winner::Yellow) => EXIT_YELLOW,
+        Some(Winner::Tie) => EXIT_TIE,
+        None => EXIT_UNFINISHED,
+    };
+    std::process::exit(code);
+}
 
-            game.make_move(items[chosen]).unwrap();
-        } else {
-            println!("Bot's Turn");
-            let bot = if game.current_colour() == Colour::Red {
-                red_bot.as_mut().unwrap()
+/// The winner and moves of a game on one line. An unfinished game has no
+/// winner, written as `null` or `none`.
+fn summary(game: &Classic, format: Output) -> String {
+    let winner = match game.winner() {
+        Some(Winner::Red) => "Red",
+        Some(Winner::Yellow) => "Yellow",
+        Some(Winner::Tie) => "Tie",
+        None => "",
+    };
+    let moves = game.history().iter().map(|&mv| write_move(mv));
+
+    match format {
+        Output::Json => {
+            let winner = if winner.is_empty() {
+                "null".to_owned()
             } else {
-                yellow_bot.as_mut().unwrap()
+                format!("\"{}\"", winner)
             };
-            let action = bot.select(&game, Duration::from_secs(think_time)).unwrap();
-            game.make_move(action).unwrap();
+            let moves = moves.map(|mv| format!("\"{}\"", mv)).collect::<Vec<_>>();
+
+            format!("{{\"winner\":{},\"moves\":[{}]}}", winner, moves.join(","))
+        }
+        Output::Plain => {
+            let winner = if winner.is_empty() { "none" } else { winner };
+
+            format!("{} {}", winner, moves.collect::<Vec<_>>().join(","))
         }
     }
+}
 
-    game.state().render();
+fn perform() {
+    let settings = prompt_settings();
+    let game = play(&settings, settings.new_game());
 
-    println!("{:?}", game.winner());
+    game.render_colored();
+
+    // resigning or the last move always finishes the game
+    if let Some(winner) = game.winner() {
+        println!("{}", winner);
+    }
 }
 
 fn main() {
-    perform();
+    if std::env::args_os().len() > 1 {
+        let args = Args::try_parse().unwrap_or_else(|e| {
+            if !e.use_stderr() {
+                // `--help` and `--version`
+                e.exit();
+            }
+
+            let _ = e.print();
+            std::process::exit(EXIT_ERROR);
+        });
+        run(args);
+    } else {
+        perform();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(args: &[&str]) -> Settings {
+        let args = Args::try_parse_from(std::iter::once("c4").chain(args.iter().copied()));
+        Settings::from_args(&args.unwrap())
+    }
+
+    #[test]
+    fn delays_reach_the_settings() {
+        let watched = settings(&["--play-as", "bot", "--delay", "250", "--clear"]);
+        assert_eq!(watched.delay, Duration::from_millis(250));
+        assert!(watched.clear);
+
+        let unwatched = settings(&["--play-as", "bot"]);
+        assert_eq!(unwatched.delay, Duration::ZERO);
+        assert!(!unwatched.clear);
+    }
 }
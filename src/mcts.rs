@@ -0,0 +1,277 @@
+//! Monte Carlo tree search, which judges moves by playing random games out
+//! from them rather than by the heuristic the alpha-beta bot relies on.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use rand::prelude::*;
+
+use crate::{Colour, Column, FlipPolicy, Game, Winner};
+
+// how much UCT favours moves it has tried less, the usual square root of two
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+// positions kept by default, about 50MB of them
+const CAPACITY: usize = 1 << 20;
+
+/// Everything besides the board that changes how a position plays out,
+/// the rules included so that games with different ones searched by the
+/// same `Mcts` don't share statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    board: u64,
+    to_move: Colour,
+    round: u8,
+    intervals: u32,
+    flip_interval: u8,
+    flip_policy: FlipPolicy,
+    win_length: usize,
+    persist_wins_through_flip: bool,
+    pop_out: bool,
+}
+
+impl Key {
+    fn new<const W: usize, const H: usize>(game: &Game<W, H>) -> Self {
+        Self {
+            board: game.state.canonical_key(),
+            to_move: game.current_colour,
+            round: game.round,
+            intervals: game.intervals,
+            flip_interval: if game.flips() { game.flip_interval } else { 0 },
+            flip_policy: game.flip_policy,
+            win_length: game.win_length,
+            persist_wins_through_flip: game.persist_wins_through_flip,
+            pop_out: game.pop_out,
+        }
+    }
+}
+
+/// How the games played through a position went, for the player who moved
+/// into it.
+#[derive(Debug, Clone, Copy, Default)]
+struct Node {
+    visits: u32,
+    /// A win counts one and a tie half.
+    score: f64,
+}
+
+impl Node {
+    /// The UCT score of playing into this node from a parent visited
+    /// `parent_visits` times.
+    fn uct(&self, parent_visits: u32) -> f64 {
+        // a position reached by another move order can have been visited
+        // more often than its parent
+        let (visits, parent_visits) = (self.visits as f64, parent_visits.max(1) as f64);
+        self.score / visits + EXPLORATION * (parent_visits.ln() / visits).sqrt()
+    }
+}
+
+/// A UCT search, sharing the statistics of positions reached by different
+/// move orders and of mirrored positions.
+///
+/// The tree stops growing once it holds `capacity` positions, after which
+/// playouts are only counted in the positions it already has.
+pub struct Mcts<const W: usize, const H: usize> {
+    rng: StdRng,
+    nodes: HashMap<Key, Node>,
+    capacity: usize,
+}
+
+impl<const W: usize, const H: usize> Default for Mcts<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize, const H: usize> Mcts<W, H> {
+    pub fn new() -> Self {
+        Self::with_rng(StdRng::from_entropy())
+    }
+
+    /// A search whose playouts are decided by `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: StdRng) -> Self {
+        Self {
+            rng,
+            nodes: HashMap::new(),
+            capacity: CAPACITY,
+        }
+    }
+
+    /// How many positions the tree holds at most, about a million by
+    /// default.
+    pub fn capacity(mut self, positions: usize) -> Self {
+        self.capacity = positions;
+        self
+    }
+
+    /// The move for the current player found by searching for `think_time`,
+    /// which is the one tried most. Only `None` if the game is over.
+    pub fn select(&mut self, game: &Game<W, H>, think_time: Duration) -> Option<Column<W>> {
+        let moves = game.legal_moves();
+        if moves.len() <= 1 {
+            return moves.first().copied();
+        }
+
+        // statistics from earlier searches are kept, which only helps if
+        // the game has moved on to one of their positions
+        let start = Instant::now();
+        loop {
+            self.iterate(game);
+
+            if start.elapsed() >= think_time {
+                break;
+            }
+        }
+
+        moves.into_iter().max_by_key(|&column| {
+            let child = game.with_move(column).unwrap();
+            self.nodes.get(&Key::new(&child)).map_or(0, |n| n.visits)
+        })
+    }
+
+    /// Forgets the statistics of every position searched so far.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+
+    /// Walks down the tree to a position it hasn't seen, plays a random game
+    /// out from there, and counts the result in every position on the way.
+    fn iterate(&mut self, root: &Game<W, H>) {
+        let mut game = root.clone();
+        // each position on the way along with the colour that moved into it
+        let mut path = vec![(Key::new(&game), game.current_colour.invert())];
+
+        while !game.is_finished() {
+            let parent_visits = self
+                .nodes
+                .get(&path.last().unwrap().0)
+                .map_or(0, |n| n.visits);
+            let mover = game.current_colour;
+
+            let children = game
+                .legal_moves()
+                .into_iter()
+                .map(|column| {
+                    let child = game.with_move(column).unwrap();
+                    let node = self.nodes.get(&Key::new(&child)).copied();
+                    (child, node)
+                })
+                .collect::<Vec<_>>();
+
+            let unvisited = (0..children.len())
+                .filter(|&idx| children[idx].1.is_none())
+                .collect::<Vec<_>>();
+
+            if let Some(&idx) = unvisited.choose(&mut self.rng) {
+                game = children[idx].0.clone();
+                path.push((Key::new(&game), mover));
+                break;
+            }
+
+            let (child, _) = children
+                .into_iter()
+                .map(|(child, node)| {
+                    let uct = node.unwrap().uct(parent_visits);
+                    (child, uct)
+                })
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))
+                .unwrap();
+
+            game = child;
+            path.push((Key::new(&game), mover));
+        }
+
+        let winner = self.playout(game);
+
+        for (key, mover) in path {
+            if !self.nodes.contains_key(&key) && self.nodes.len() >= self.capacity {
+                continue;
+            }
+
+            let node = self.nodes.entry(key).or_default();
+            node.visits += 1;
+            node.score += match winner {
+                Some(Winner::Tie) => 0.5,
+                Some(w) if w.to_colour() == Some(mover) => 1.0,
+                _ => 0.0,
+            };
+        }
+    }
+
+    /// Plays random drops until the game is over.
+    fn playout(&mut self, mut game: Game<W, H>) -> Option<Winner> {
+        while let Some(&column) = game.legal_moves().choose(&mut self.rng) {
+            game.make_move(column).unwrap();
+        }
+
+        game.winner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Classic;
+
+    fn play(moves: &[Column<7>]) -> Classic {
+        let mut game = Classic::new(Colour::Red, false);
+        for &column in moves {
+            game.make_move(column).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn mcts_takes_a_win_in_one() {
+        // red has A to C along the bottom
+        let game = play(&[
+            Column::A,
+            Column::A,
+            Column::B,
+            Column::B,
+            Column::C,
+            Column::C,
+        ]);
+
+        let mut mcts = Mcts::with_seed(88);
+        assert_eq!(
+            mcts.select(&game, Duration::from_millis(200)),
+            Some(Column::D)
+        );
+    }
+
+    #[test]
+    fn mcts_finds_a_win_in_two() {
+        // D gives red three along the bottom open at both ends, which yellow
+        // can only block one of
+        let game = play(&[Column::B, Column::B, Column::C, Column::C]);
+
+        let mut mcts = Mcts::with_seed(88);
+        assert_eq!(mcts.select(&game, Duration::from_secs(1)), Some(Column::D));
+    }
+
+    #[test]
+    fn the_tree_stops_growing_at_its_capacity() {
+        let game = Classic::new(Colour::Red, false);
+
+        let mut mcts = Mcts::with_seed(88).capacity(100);
+        for _ in 0..200 {
+            mcts.iterate(&game);
+        }
+        assert_eq!(mcts.nodes.len(), 100);
+    }
+
+    #[test]
+    fn games_with_other_rules_dont_share_positions() {
+        let classic = Classic::new(Colour::Red, false);
+        let connect_five = Classic::with_win_length(Colour::Red, false, 5);
+
+        let mut mcts = Mcts::with_seed(88);
+        mcts.iterate(&classic);
+        assert!(mcts.nodes.contains_key(&Key::new(&classic)));
+        assert!(!mcts.nodes.contains_key(&Key::new(&connect_five)));
+    }
+}
@@ -0,0 +1,289 @@
+//! Two player games over TCP.
+//!
+//! The host keeps the authoritative game and plays red. After connecting it
+//! sends `START <first colour> <flip|noflip>`, then every move either side
+//! makes is applied by the host and sent to the client as `MOVE <column>`,
+//! followed by `OVER <R|Y|TIE>` if it finished the game. The client sends
+//! just the column it wants to play, which the host answers with `ERROR
+//! <reason>` if it can't be played.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use crate::{Classic, Colour, Column, MoveError, Winner, BOARD_WIDTH};
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    /// The other player went away, which counts as them resigning.
+    Disconnected,
+    /// The host refused the move, giving its reason.
+    Rejected(String),
+    /// A move that this side can't make.
+    Illegal(MoveError<BOARD_WIDTH>),
+    /// A line that doesn't follow the protocol.
+    Protocol(String),
+}
+
+impl std::fmt::Display for NetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetError::Io(e) => write!(f, "{}", e),
+            NetError::Disconnected => write!(f, "the other player disconnected"),
+            NetError::Rejected(reason) => write!(f, "the move was rejected: {}", reason),
+            NetError::Illegal(e) => write!(f, "{}", e),
+            NetError::Protocol(line) => write!(f, "unexpected message {:?}", line),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<io::Error> for NetError {
+    fn from(e: io::Error) -> Self {
+        NetError::Io(e)
+    }
+}
+
+/// One side of a networked game.
+pub struct NetGame {
+    game: Classic,
+    colour: Colour,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+/// Waits for a client to connect to `listener` and starts a game with them.
+pub fn host(listener: &TcpListener, first: Colour, flipping: bool) -> Result<NetGame, NetError> {
+    let (stream, _) = listener.accept()?;
+
+    let mut game = NetGame::new(stream, Classic::new(first, flipping), Colour::Red)?;
+    let flipping = if flipping { "flip" } else { "noflip" };
+    game.send(&format!("START {} {}", first, flipping))?;
+
+    Ok(game)
+}
+
+/// Connects to a host, playing yellow in the game it starts.
+pub fn join<A: ToSocketAddrs>(addr: A) -> Result<NetGame, NetError> {
+    let stream = TcpStream::connect(addr)?;
+    let mut game = NetGame::new(stream, Classic::new(Colour::Red, false), Colour::Yellow)?;
+
+    let line = game.receive()?;
+    let start = match line.split(' ').collect::<Vec<_>>()[..] {
+        ["START", first, flipping] => parse_colour(first).zip(parse_flipping(flipping)),
+        _ => None,
+    };
+    let (first, flipping) = start.ok_or(NetError::Protocol(line))?;
+
+    game.game = Classic::new(first, flipping);
+
+    Ok(game)
+}
+
+fn parse_colour(s: &str) -> Option<Colour> {
+    match s {
+        "R" => Some(Colour::Red),
+        "Y" => Some(Colour::Yellow),
+        _ => None,
+    }
+}
+
+fn parse_flipping(s: &str) -> Option<bool> {
+    match s {
+        "flip" => Some(true),
+        "noflip" => Some(false),
+        _ => None,
+    }
+}
+
+fn winner_name(winner: Winner) -> String {
+    match winner.to_colour() {
+        Some(colour) => colour.to_string(),
+        None => "TIE".to_owned(),
+    }
+}
+
+impl NetGame {
+    fn new(stream: TcpStream, game: Classic, colour: Colour) -> io::Result<Self> {
+        Ok(Self {
+            game,
+            colour,
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    pub fn game(&self) -> &Classic {
+        &self.game
+    }
+
+    /// The colour played on this side.
+    pub fn colour(&self) -> Colour {
+        self.colour
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.colour == Colour::Red
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), NetError> {
+        let result = writeln!(self.writer, "{}", line);
+        result.map_err(|e| self.io_error(e))
+    }
+
+    fn receive(&mut self) -> Result<String, NetError> {
+        let mut line = String::new();
+
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Err(self.resign_opponent()),
+            Ok(_) => Ok(line.trim().to_owned()),
+            Err(e) => Err(self.io_error(e)),
+        }
+    }
+
+    fn io_error(&mut self, e: io::Error) -> NetError {
+        match e.kind() {
+            io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted => self.resign_opponent(),
+            _ => NetError::Io(e),
+        }
+    }
+
+    fn resign_opponent(&mut self) -> NetError {
+        // a game that's already over stays as it ended
+        let _ = self.game.resign(self.colour.invert());
+
+        NetError::Disconnected
+    }
+
+    /// Applies a move on the host's game and tells the client about it.
+    fn apply(&mut self, column: Column<BOARD_WIDTH>) -> Result<(), NetError> {
+        self.game.make_move(column).map_err(NetError::Illegal)?;
+        self.send(&format!("MOVE {}", column))?;
+
+        if let Some(winner) = self.game.winner() {
+            self.send(&format!("OVER {}", winner_name(winner)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a move the host has applied, along with the end of the game if
+    /// it finished it.
+    fn expect_move(&mut self, line: String) -> Result<Column<BOARD_WIDTH>, NetError> {
+        let column = match line.strip_prefix("MOVE ").map(str::parse) {
+            Some(Ok(column)) => column,
+            _ => return Err(NetError::Protocol(line)),
+        };
+
+        self.game.make_move(column).map_err(NetError::Illegal)?;
+
+        if let Some(winner) = self.game.winner() {
+            let line = self.receive()?;
+
+            if line != format!("OVER {}", winner_name(winner)) {
+                return Err(NetError::Protocol(line));
+            }
+        }
+
+        Ok(column)
+    }
+
+    /// Plays a move for this side.
+    pub fn play(&mut self, column: Column<BOARD_WIDTH>) -> Result<(), NetError> {
+        if self.game.current_colour() != self.colour {
+            return Err(NetError::Rejected("it's not your turn".to_owned()));
+        }
+
+        if self.is_host() {
+            return self.apply(column);
+        }
+
+        self.game.with_move(column).map_err(NetError::Illegal)?;
+        self.send(&column.to_string())?;
+
+        let line = self.receive()?;
+
+        match line.strip_prefix("ERROR ") {
+            Some(reason) => Err(NetError::Rejected(reason.to_owned())),
+            None => self.expect_move(line).map(|_| ()),
+        }
+    }
+
+    /// Waits for the other player to move, returning the column they played.
+    ///
+    /// The host answers illegal moves from the client with an error and
+    /// carries on waiting.
+    pub fn wait_for_move(&mut self) -> Result<Column<BOARD_WIDTH>, NetError> {
+        if self.game.current_colour() == self.colour {
+            return Err(NetError::Rejected("it's your turn".to_owned()));
+        }
+
+        if !self.is_host() {
+            let line = self.receive()?;
+            return self.expect_move(line);
+        }
+
+        loop {
+            let line = self.receive()?;
+
+            let column = match line.parse::<Column<BOARD_WIDTH>>() {
+                Ok(column) => column,
+                Err(e) => {
+                    self.send(&format!("ERROR {}", e))?;
+                    continue;
+                }
+            };
+
+            if !self.game.legal_moves().contains(&column) {
+                let reason = match self.game.with_move(column) {
+                    Err(e) => e.to_string(),
+                    Ok(_) => format!("{} can't be played", column),
+                };
+                self.send(&format!("ERROR {}", reason))?;
+            } else {
+                self.apply(column)?;
+                return Ok(column);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_game_plays_out_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = std::thread::spawn(move || {
+            let mut client = join(addr).unwrap();
+            assert_eq!(client.colour(), Colour::Yellow);
+
+            // moving out of turn is refused before anything is sent
+            assert!(matches!(client.play(Column::B), Err(NetError::Rejected(_))));
+
+            for _ in 0..3 {
+                assert_eq!(client.wait_for_move().unwrap(), Column::A);
+                client.play(Column::B).unwrap();
+            }
+            assert_eq!(client.wait_for_move().unwrap(), Column::A);
+
+            client.game().winner()
+        });
+
+        let mut host = host(&listener, Colour::Red, false).unwrap();
+        for _ in 0..3 {
+            host.play(Column::A).unwrap();
+            assert_eq!(host.wait_for_move().unwrap(), Column::B);
+        }
+        host.play(Column::A).unwrap();
+
+        assert_eq!(host.game().winner(), Some(Winner::Red));
+        assert_eq!(client.join().unwrap(), Some(Winner::Red));
+    }
+}
@@ -0,0 +1,288 @@
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::{Board, Colour, Column, Gravity};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The number of columns given, which didn't match the board width.
+    WrongColumnCount(usize),
+    /// A column held more discs than the board is tall.
+    ColumnTooTall(usize),
+    InvalidDisc(char),
+    InvalidGravity(String),
+    /// A disc that isn't resting against the wall gravity points at, or on
+    /// another disc.
+    Floating,
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseError::WrongColumnCount(n) => write!(f, "found {} columns", n),
+            ParseError::ColumnTooTall(c) => {
+                write!(
+                    f,
+                    "column {} holds too many discs",
+                    (b'A' + *c as u8) as char
+                )
+            }
+            ParseError::InvalidDisc(c) => write!(f, "{:?} is not a disc", c),
+            ParseError::InvalidGravity(s) => write!(f, "{:?} is not a gravity direction", s),
+            ParseError::Floating => write!(f, "a disc isn't supported by gravity"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidBoard<const W: usize> {
+    /// The first disc, going up each column in turn, that gravity would move.
+    Floating(Column<W>, u8),
+}
+
+impl<const W: usize> core::fmt::Display for InvalidBoard<W> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvalidBoard::Floating(column, row) => {
+                write!(
+                    f,
+                    "the disc in column {} row {} isn't supported",
+                    column, row
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const W: usize> std::error::Error for InvalidBoard<W> {}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// Builds a board from its rows as they're drawn, starting with the top
+    /// one.
+    pub fn from_grid(
+        rows: &[[Option<Colour>; W]; H],
+        gravity: Gravity,
+    ) -> Result<Self, InvalidBoard<W>> {
+        let mut board = Board::with_gravity(gravity);
+
+        for (idx, row) in rows.iter().enumerate() {
+            for (column, &disc) in Column::<W>::all().zip(row) {
+                board.set_cell(column, (H - 1 - idx) as u8, disc);
+            }
+        }
+
+        match board.floating_disc() {
+            Some((column, row)) => Err(InvalidBoard::Floating(column, row)),
+            None => Ok(board),
+        }
+    }
+
+    /// The first disc, going up each column in turn, that gravity would move.
+    pub(crate) fn floating_disc(&self) -> Option<(Column<W>, u8)> {
+        let mut settled = vec![false; W * H];
+        for lane in 0..self.lane_count() {
+            for depth in 0..self.lane_height(lane) {
+                let (column, row) = self.lane_cell(lane, depth);
+                settled[Self::index_of(column, row)] = true;
+            }
+        }
+
+        self.iter_cells()
+            .find(|&(column, row, disc)| disc.is_some() && !settled[Self::index_of(column, row)])
+            .map(|(column, row, _)| (column, row))
+    }
+
+    /// Writes the board on one line: each column's discs from bottom to
+    /// top as `R` or `Y` (`-` when empty), separated by `/`, followed by
+    /// `d`, `u`, `l` or `r` for the direction of gravity, eg.
+    /// `-/-/-/RYR/Y/-/-/d`.
+    ///
+    /// Under sideways gravity columns can have gaps, so every cell in them is
+    /// written with `.` for the empty ones, eg. `RY..../Y...../-/-/-/-/-/l`.
+    ///
+    /// Blocked cells aren't written, so they're lost by `from_notation`.
+    pub fn to_notation(&self) -> String {
+        let mut out = String::new();
+
+        for column in Column::<W>::all() {
+            let cells = (0..H as u8).map(|row| self.piece_at(column, row));
+
+            let discs = if self.gravity.is_vertical() {
+                cells.flatten().map(|p| p.to_string()).collect::<String>()
+            } else {
+                cells
+                    .map(|p| p.map_or(".".to_owned(), |p| p.to_string()))
+                    .collect::<String>()
+            };
+
+            if discs.chars().all(|c| c == '.') {
+                out.push('-');
+            } else {
+                out.push_str(&discs);
+            }
+
+            out.push('/');
+        }
+
+        out.push(match self.gravity {
+            Gravity::Down => 'd',
+            Gravity::Up => 'u',
+            Gravity::Left => 'l',
+            Gravity::Right => 'r',
+        });
+
+        out
+    }
+
+    pub fn from_notation(s: &str) -> Result<Self, ParseError> {
+        let mut segments = s.trim().split('/').collect::<Vec<_>>();
+
+        let gravity = match segments.pop() {
+            Some("d") => Gravity::Down,
+            Some("u") => Gravity::Up,
+            Some("l") => Gravity::Left,
+            Some("r") => Gravity::Right,
+            other => return Err(ParseError::InvalidGravity(other.unwrap_or("").to_owned())),
+        };
+
+        if segments.len() != W {
+            return Err(ParseError::WrongColumnCount(segments.len()));
+        }
+
+        let mut board = Board::with_gravity(gravity);
+
+        for (column, segment) in Column::<W>::all().zip(segments) {
+            let cells = if segment == "-" { "" } else { segment };
+
+            if cells.chars().count() > H {
+                return Err(ParseError::ColumnTooTall(column.to_idx()));
+            }
+
+            let cells = cells
+                .chars()
+                .map(|c| match c {
+                    'R' => Ok(Some(Colour::Red)),
+                    'Y' => Ok(Some(Colour::Yellow)),
+                    '.' if !gravity.is_vertical() => Ok(None),
+                    _ => Err(ParseError::InvalidDisc(c)),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // discs are written bottom to top, which is away from the wall
+            // unless gravity points up
+            let offset = match gravity {
+                Gravity::Up => H - cells.len(),
+                _ => 0,
+            };
+
+            for (row, disc) in cells.into_iter().enumerate() {
+                board.set_cell(column, (offset + row) as u8, disc);
+            }
+        }
+
+        let discs = (0..W * H).filter(|&idx| board.present[idx]).count();
+        let settled = (0..board.lane_count())
+            .map(|lane| board.lane_height(lane))
+            .sum::<usize>();
+
+        if discs != settled {
+            return Err(ParseError::Floating);
+        }
+
+        Ok(board)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::ClassicBoard;
+
+    #[test]
+    fn notation_round_trips() {
+        for notation in [
+            "-/-/-/-/-/-/-/d",
+            "-/-/-/RYR/Y/-/-/d",
+            "RYRYRY/-/Y/-/-/-/R/d",
+            "-/-/-/RYR/Y/-/-/u",
+            "RY..../Y...../-/-/-/-/-/l",
+            "-/-/-/-/-/..Y.../RRRYYY/r",
+        ] {
+            let board = ClassicBoard::from_notation(notation).unwrap();
+            assert_eq!(board.to_notation(), notation);
+        }
+    }
+
+    #[test]
+    fn parsed_boards_have_the_right_heights() {
+        let board = ClassicBoard::from_notation("-/-/-/RYR/Y/-/-/d").unwrap();
+        assert_eq!(board.column_height(Column::D), 3);
+        assert_eq!(board.drop_row(Column::D), Some(3));
+        assert_eq!(board.piece_at(Column::D, 2), Some(Colour::Red));
+
+        // gravity up stacks the discs against the top, the last written
+        // highest
+        let board = ClassicBoard::from_notation("-/-/-/RYR/Y/-/-/u").unwrap();
+        assert_eq!(board.column_height(Column::D), 3);
+        assert_eq!(board.drop_row(Column::D), Some(2));
+        assert_eq!(board.piece_at(Column::D, 3), Some(Colour::Red));
+        assert_eq!(board.piece_at(Column::D, 4), Some(Colour::Yellow));
+        assert_eq!(board.piece_at(Column::E, 5), Some(Colour::Yellow));
+    }
+
+    #[test]
+    fn bad_notation_is_rejected() {
+        let parse = ClassicBoard::from_notation;
+
+        assert_eq!(parse("-/-/-/-/-/-/d"), Err(ParseError::WrongColumnCount(6)));
+        assert_eq!(
+            parse("-/-/-/RYRYRYR/-/-/-/d"),
+            Err(ParseError::ColumnTooTall(3))
+        );
+        assert_eq!(parse("-/-/-/RX/-/-/-/d"), Err(ParseError::InvalidDisc('X')));
+        assert_eq!(parse("-/-/-/R./-/-/-/d"), Err(ParseError::InvalidDisc('.')));
+        assert_eq!(
+            parse("-/-/-/-/-/-/-/x"),
+            Err(ParseError::InvalidGravity("x".to_owned()))
+        );
+        assert_eq!(parse("-/R/-/-/-/-/-/l"), Err(ParseError::Floating));
+    }
+
+    #[test]
+    fn grids_build_settled_boards() {
+        const R: Option<Colour> = Some(Colour::Red);
+        const Y: Option<Colour> = Some(Colour::Yellow);
+        const E: Option<Colour> = None;
+
+        let mut rows = [[E; 7]; 6];
+        rows[5] = [E, E, R, Y, E, E, E];
+        rows[4] = [E, E, E, R, E, E, E];
+
+        let board = ClassicBoard::from_grid(&rows, Gravity::Down).unwrap();
+        assert_eq!(
+            board,
+            ClassicBoard::from_notation("-/-/R/YR/-/-/-/d").unwrap()
+        );
+        assert_eq!(board.drop_row(Column::D), Some(2));
+
+        // the same rows float under gravity pointing up
+        assert_eq!(
+            ClassicBoard::from_grid(&rows, Gravity::Up),
+            Err(InvalidBoard::Floating(Column::C, 0))
+        );
+
+        rows[5][3] = E;
+        assert_eq!(
+            ClassicBoard::from_grid(&rows, Gravity::Down),
+            Err(InvalidBoard::Floating(Column::D, 1))
+        );
+    }
+}
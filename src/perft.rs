@@ -0,0 +1,78 @@
+use alloc::vec::Vec;
+
+use crate::{Column, Game};
+
+/// The number of move sequences `depth` moves long that can be played from
+/// `game`, for checking move generation. Lines that finish the game early
+/// aren't counted.
+pub fn perft<const W: usize, const H: usize>(game: &Game<W, H>, depth: usize) -> u64 {
+    count(&mut game.clone(), depth)
+}
+
+/// Like `perft`, split by the first move of each sequence.
+pub fn perft_detailed<const W: usize, const H: usize>(
+    game: &Game<W, H>,
+    depth: usize,
+) -> Vec<(Column<W>, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    let mut game = game.clone();
+
+    game.legal_moves()
+        .into_iter()
+        .map(|column| {
+            game.make_move(column).unwrap();
+            let nodes = count(&mut game, depth - 1);
+            game.undo_move().unwrap();
+
+            (column, nodes)
+        })
+        .collect()
+}
+
+fn count<const W: usize, const H: usize>(game: &mut Game<W, H>, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut nodes = 0;
+
+    for column in game.legal_moves() {
+        game.make_move(column).unwrap();
+        nodes += count(game, depth - 1);
+        game.undo_move().unwrap();
+    }
+
+    nodes
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{Classic, Colour};
+
+    #[test]
+    fn perft_counts_every_line_on_an_empty_board() {
+        let game = Classic::new(Colour::Red, false);
+
+        assert_eq!(perft(&game, 0), 1);
+        assert_eq!(perft(&game, 1), 7);
+        // nothing fills a column or wins in the first five moves
+        assert_eq!(perft(&game, 5), 16807);
+    }
+
+    #[test]
+    fn perft_detailed_adds_up_to_perft() {
+        let game = Classic::new(Colour::Red, true);
+        let detailed = perft_detailed(&game, 4);
+
+        assert_eq!(detailed.len(), 7);
+        assert_eq!(
+            detailed.iter().map(|&(_, nodes)| nodes).sum::<u64>(),
+            perft(&game, 4)
+        );
+        assert!(perft_detailed(&game, 0).is_empty());
+    }
+}
@@ -0,0 +1,52 @@
+use rand::prelude::*;
+
+use crate::{Board, Colour, Game};
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// A board reached by random moves, see `Game::random`.
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Game::random(rng, false).state
+    }
+}
+
+impl<const W: usize, const H: usize> Game<W, H> {
+    /// A game made of a random number of random legal drops, which may have
+    /// finished.
+    pub fn random(rng: &mut impl Rng, flipping: bool) -> Self {
+        let mut game = Game::new(Colour::Red, flipping);
+        let moves = rng.gen_range(0..=W * H);
+
+        for _ in 0..moves {
+            match game.legal_moves().choose(rng) {
+                Some(&column) => game.make_move(column).unwrap(),
+                None => break,
+            }
+        }
+
+        game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ClassicBoard;
+
+    #[test]
+    fn random_boards_have_as_many_reds_as_yellows_give_or_take_one() {
+        let mut rng = StdRng::seed_from_u64(40);
+
+        for _ in 0..1000 {
+            let board = ClassicBoard::random(&mut rng);
+            let red = board.count_pieces_of(Colour::Red);
+            let yellow = board.count_pieces_of(Colour::Yellow);
+
+            assert!(
+                red.abs_diff(yellow) <= 1,
+                "{} red and {} yellow",
+                red,
+                yellow
+            );
+        }
+    }
+}
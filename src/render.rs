@@ -0,0 +1,435 @@
+use std::fmt;
+use std::io::IsTerminal;
+
+use crate::{colour_name, Board, Colour, Column, Game, Gravity, Winner};
+
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const DIM: &str = "\x1b[2m";
+const HIGHLIGHT: &str = "\x1b[1;7m";
+const UNDERLINE: &str = "\x1b[4m";
+const RESET: &str = "\x1b[0m";
+
+/// The characters drawn for discs and empty cells. Blocked cells are always
+/// drawn as `#`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlyphTheme {
+    pub red: char,
+    pub yellow: char,
+    pub empty: char,
+}
+
+impl GlyphTheme {
+    /// The letters `Board`'s `Display` impl uses.
+    pub const ASCII: Self = Self {
+        red: 'R',
+        yellow: 'Y',
+        empty: '_',
+    };
+
+    /// Filled and hollow circles, for terminals that can draw them.
+    pub const UNICODE: Self = Self {
+        red: '\u{25cf}',
+        yellow: '\u{25cb}',
+        empty: '\u{b7}',
+    };
+
+    fn disc(&self, colour: Colour) -> char {
+        match colour {
+            Colour::Red => self.red,
+            Colour::Yellow => self.yellow,
+        }
+    }
+}
+
+impl Default for GlyphTheme {
+    fn default() -> Self {
+        Self::ASCII
+    }
+}
+
+/// What to draw around a board besides its cells, and what to draw them
+/// with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Column letters above the board.
+    pub labels_top: bool,
+    /// Column letters below the board.
+    pub labels_bottom: bool,
+    /// Row numbers left of the board, counting from 1 at the bottom.
+    pub row_indices: bool,
+    /// Draw the winning line of a finished game highlighted, which only
+    /// `Game::render_with` knows about.
+    pub highlight_win: bool,
+    /// Underline the disc dropped by the last move, which only
+    /// `Game::render_with` knows about.
+    pub highlight_last: bool,
+    pub glyphs: GlyphTheme,
+}
+
+/// The look of `render_colored`, with column letters only below the board.
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            labels_top: false,
+            labels_bottom: true,
+            row_indices: false,
+            highlight_win: true,
+            highlight_last: true,
+            glyphs: GlyphTheme::default(),
+        }
+    }
+}
+
+/// Renders a board with ANSI colours, in the same layout as `Board`'s
+/// `Display` impl unless given other `RenderOptions`.
+pub struct ColoredBoard<'a, const W: usize, const H: usize> {
+    board: &'a Board<W, H>,
+    highlight: Vec<(Column<W>, u8)>,
+    last_move: Option<(Column<W>, u8)>,
+    color: bool,
+    options: RenderOptions,
+}
+
+impl<'a, const W: usize, const H: usize> ColoredBoard<'a, W, H> {
+    pub fn new(board: &'a Board<W, H>) -> Self {
+        Self {
+            board,
+            highlight: Vec::new(),
+            last_move: None,
+            color: true,
+            options: RenderOptions::default(),
+        }
+    }
+
+    /// Draws these cells bold and inverted, eg. the winning line.
+    pub fn highlight(mut self, cells: impl IntoIterator<Item = (Column<W>, u8)>) -> Self {
+        self.highlight.extend(cells);
+        self
+    }
+
+    /// Draws this cell underlined, eg. the disc the last move dropped.
+    pub fn last_move(mut self, cell: Option<(Column<W>, u8)>) -> Self {
+        self.last_move = cell;
+        self
+    }
+
+    /// With colour off and the default options the output matches the plain
+    /// `Display` impl.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Only colour the output when stdout is a terminal.
+    pub fn color_if_tty(self) -> Self {
+        self.color(std::io::stdout().is_terminal())
+    }
+}
+
+impl<'a, const W: usize, const H: usize> ColoredBoard<'a, W, H> {
+    /// Where to put labels around the board, which otherwise only has the
+    /// column letters below it.
+    pub fn options(mut self, options: RenderOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The column letters, indented past the row numbers if there are any.
+    fn write_labels(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.options.row_indices {
+            write!(f, "{:width$} ", "", width = row_index_width::<H>())?;
+        }
+
+        for c in Column::<W>::all() {
+            write!(f, "{}", c)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_cell(&self, f: &mut fmt::Formatter<'_>, col: Column<W>, row: u8) -> fmt::Result {
+        let piece = self.board.piece_at(col, row);
+        let glyphs = self.options.glyphs;
+
+        if !self.color {
+            return match piece {
+                Some(p) => write!(f, "{}", glyphs.disc(p)),
+                None if self.board.is_blocked(col, row) => write!(f, "#"),
+                None => write!(f, "{}", glyphs.empty),
+            };
+        }
+
+        let piece = match piece {
+            Some(p) => p,
+            None if self.board.is_blocked(col, row) => return write!(f, "{}#{}", DIM, RESET),
+            None => return write!(f, "{}{}{}", DIM, glyphs.empty, RESET),
+        };
+
+        if self.highlight.contains(&(col, row)) {
+            write!(f, "{}", HIGHLIGHT)?;
+        }
+        if self.last_move == Some((col, row)) {
+            write!(f, "{}", UNDERLINE)?;
+        }
+
+        let fg = match piece {
+            Colour::Red => RED,
+            Colour::Yellow => YELLOW,
+        };
+
+        write!(f, "{}{}{}", fg, glyphs.disc(piece), RESET)
+    }
+}
+
+/// Rows are numbered from 1 at the bottom, right aligned.
+fn row_index_width<const H: usize>() -> usize {
+    H.to_string().len()
+}
+
+impl<'a, const W: usize, const H: usize> fmt::Display for ColoredBoard<'a, W, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.options.labels_top {
+            self.write_labels(f)?;
+            writeln!(f)?;
+        }
+
+        for i in (0..H as u8).rev() {
+            if self.options.row_indices {
+                write!(f, "{:>width$} ", i + 1, width = row_index_width::<H>())?;
+            }
+
+            for col in Column::<W>::all() {
+                self.write_cell(f, col, i)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        if self.options.labels_bottom {
+            self.write_labels(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    pub fn colored(&self) -> ColoredBoard<'_, W, H> {
+        ColoredBoard::new(self)
+    }
+
+    /// The board in words for screen readers, going up each column in turn,
+    /// eg. `Column A: empty. Column B: Red at the bottom, Yellow above it.`
+    /// Rows are numbered from 1 at the bottom.
+    pub fn describe(&self) -> String {
+        let mut sentences = Vec::new();
+
+        let direction = match self.gravity {
+            Gravity::Down => None,
+            Gravity::Up => Some("up"),
+            Gravity::Left => Some("left"),
+            Gravity::Right => Some("right"),
+        };
+        if let Some(direction) = direction {
+            sentences.push(format!("Gravity pulls {}.", direction));
+        }
+
+        for column in Column::<W>::all() {
+            let mut discs = Vec::new();
+            let mut below = None;
+
+            for row in 0..H as u8 {
+                let colour = match self.piece_at(column, row) {
+                    Some(colour) => colour_name(colour),
+                    None => continue,
+                };
+
+                discs.push(match (row, below) {
+                    (0, _) => format!("{} at the bottom", colour),
+                    (_, Some(b)) if b + 1 == row => format!("{} above it", colour),
+                    _ => format!("{} in row {}", colour, row + 1),
+                });
+                below = Some(row);
+            }
+
+            if discs.is_empty() {
+                sentences.push(format!("Column {}: empty.", column));
+            } else {
+                sentences.push(format!("Column {}: {}.", column, discs.join(", ")));
+            }
+        }
+
+        sentences.join(" ")
+    }
+
+    pub fn render_colored(&self) {
+        self.render_with(RenderOptions::default());
+    }
+
+    /// Like `render_colored`, with labels placed by `options`.
+    pub fn render_with(&self, options: RenderOptions) {
+        println!("{}", self.colored().options(options).color_if_tty());
+    }
+}
+
+impl<const W: usize, const H: usize> Game<W, H> {
+    /// Like `Board::render_colored`, highlighting the winning line if there
+    /// is one.
+    pub fn render_colored(&self) {
+        self.render_with(RenderOptions::default());
+    }
+
+    /// Like `Board::render_with`, highlighting the winning line if there is
+    /// one and the last disc dropped, as far as `options` asks for them.
+    pub fn render_with(&self, options: RenderOptions) {
+        let line = self
+            .winning_line()
+            .filter(|_| options.highlight_win)
+            .map(|(_, line)| line)
+            .unwrap_or_default();
+        let last_move = self.last_move().filter(|_| options.highlight_last);
+
+        println!(
+            "{}",
+            self.state()
+                .colored()
+                .highlight(line)
+                .last_move(last_move)
+                .options(options)
+                .color_if_tty()
+        );
+    }
+
+    /// Like `Board::describe`, starting with whose turn it is or how the
+    /// game ended.
+    pub fn describe(&self) -> String {
+        let status = match self.winner() {
+            Some(Winner::Tie) => "The game is a tie.".to_owned(),
+            Some(winner) => format!("{} has won.", colour_name(winner.to_colour().unwrap())),
+            None => format!("{} to move.", colour_name(self.current_colour())),
+        };
+
+        format!("{} {}", status, self.state().describe())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ClassicBoard;
+
+    fn board(notation: &str) -> ClassicBoard {
+        Board::from_notation(notation).unwrap()
+    }
+
+    #[test]
+    fn uncoloured_boards_look_like_their_display() {
+        let board = board("RY/Y/-/R/-/-/-/d");
+        assert_eq!(board.colored().color(false).to_string(), board.to_string());
+    }
+
+    #[test]
+    fn discs_are_coloured_and_empty_cells_dimmed() {
+        let rendered = board("R/Y/-/-/-/-/-/d").colored().to_string();
+        let bottom = rendered.lines().nth(5).unwrap();
+
+        assert_eq!(
+            bottom,
+            format!(
+                "{red}R{reset}{yellow}Y{reset}{empty}{empty}{empty}{empty}{empty}",
+                red = RED,
+                yellow = YELLOW,
+                reset = RESET,
+                empty = format!("{}_{}", DIM, RESET)
+            )
+        );
+    }
+
+    #[test]
+    fn highlighted_cells_are_drawn_inverted() {
+        let board = board("R/-/-/-/-/-/-/d");
+
+        let rendered = board.colored().highlight(vec![(Column::A, 0)]).to_string();
+        assert!(rendered.contains(&format!("{}{}R", HIGHLIGHT, RED)));
+
+        let rendered = board.colored().to_string();
+        assert!(!rendered.contains(HIGHLIGHT));
+    }
+
+    #[test]
+    fn boards_are_described_in_words() {
+        let board = Board::<3, 3>::from_notation("-/RY/Y/d").unwrap();
+        assert_eq!(
+            board.describe(),
+            "Column A: empty. Column B: Red at the bottom, Yellow above it. \
+             Column C: Yellow at the bottom."
+        );
+
+        let board = Board::<3, 3>::from_notation("-/RY/Y/u").unwrap();
+        assert_eq!(
+            board.describe(),
+            "Gravity pulls up. Column A: empty. Column B: Red in row 2, Yellow above it. \
+             Column C: Yellow in row 3."
+        );
+    }
+
+    #[test]
+    fn games_are_described_starting_with_whose_turn_it_is() {
+        let mut game = crate::Classic::new(Colour::Red, false);
+        assert!(game.describe().starts_with("Red to move. Column A: empty."));
+
+        for _ in 0..3 {
+            game.make_move(Column::A).unwrap();
+            game.make_move(Column::B).unwrap();
+        }
+        game.make_move(Column::A).unwrap();
+        assert!(game
+            .describe()
+            .starts_with("Red has won. Column A: Red at the bottom"));
+    }
+
+    #[test]
+    fn row_numbers_and_labels_on_both_sides() {
+        let options = RenderOptions {
+            labels_top: true,
+            labels_bottom: true,
+            row_indices: true,
+            ..RenderOptions::default()
+        };
+        let rendered = board("RY/Y/-/R/-/-/-/d")
+            .colored()
+            .color(false)
+            .options(options)
+            .to_string();
+        assert_eq!(
+            rendered,
+            "  ABCDEFG\n\
+             6 _______\n\
+             5 _______\n\
+             4 _______\n\
+             3 _______\n\
+             2 Y______\n\
+             1 RY_R___\n\
+             \x20 ABCDEFG"
+        );
+    }
+
+    #[test]
+    fn unicode_glyphs_draw_circles() {
+        let options = RenderOptions {
+            glyphs: GlyphTheme::UNICODE,
+            ..RenderOptions::default()
+        };
+        let rendered = board("RY/Y/-/R/-/-/-/d")
+            .colored()
+            .color(false)
+            .options(options)
+            .to_string();
+
+        let mut lines = rendered.lines().skip(4);
+        assert_eq!(lines.next(), Some("○······"));
+        assert_eq!(lines.next(), Some("●○·●···"));
+        assert_eq!(lines.next(), Some("ABCDEFG"));
+    }
+}
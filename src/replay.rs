@@ -0,0 +1,175 @@
+use crate::{Colour, Column, Game, MoveError};
+
+impl<const W: usize, const H: usize> Game<W, H> {
+    /// Plays `moves` on a new game, giving the index of the first illegal
+    /// move along with why it was illegal.
+    pub fn replay(
+        moves: &[Column<W>],
+        starting_colour: Colour,
+        flipping: bool,
+    ) -> Result<Self, (usize, MoveError<W>)> {
+        let mut game = Self::new(starting_colour, flipping);
+
+        for (idx, &column) in moves.iter().enumerate() {
+            game.make_move(column).map_err(|e| (idx, e))?;
+        }
+
+        Ok(game)
+    }
+
+    /// Plays `moves` on this game, or none of them if one is illegal, giving
+    /// its index along with why it was illegal.
+    pub fn apply_moves(&mut self, moves: &[Column<W>]) -> Result<(), (usize, MoveError<W>)> {
+        let before = self.clone();
+
+        for (idx, &column) in moves.iter().enumerate() {
+            if let Err(e) = self.make_move(column) {
+                *self = before;
+                return Err((idx, e));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn replay_iter(
+        moves: &[Column<W>],
+        starting_colour: Colour,
+        flipping: bool,
+    ) -> ReplayIter<'_, W, H> {
+        ReplayIter {
+            game: Self::new(starting_colour, flipping),
+            moves: moves.iter().enumerate(),
+            failed: false,
+        }
+    }
+
+    /// The new game followed by the game after each of `moves`, stopping
+    /// before the first illegal one.
+    pub fn positions(
+        moves: &[Column<W>],
+        starting_colour: Colour,
+        flipping: bool,
+    ) -> impl Iterator<Item = Self> + '_ {
+        core::iter::once(Self::new(starting_colour, flipping))
+            .chain(Self::replay_iter(moves, starting_colour, flipping).map_while(Result::ok))
+    }
+}
+
+/// Yields the game after each move of a replay, stopping after the first
+/// illegal move.
+pub struct ReplayIter<'a, const W: usize, const H: usize> {
+    game: Game<W, H>,
+    moves: core::iter::Enumerate<core::slice::Iter<'a, Column<W>>>,
+    failed: bool,
+}
+
+impl<'a, const W: usize, const H: usize> Iterator for ReplayIter<'a, W, H> {
+    type Item = Result<Game<W, H>, (usize, MoveError<W>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let (idx, &column) = self.moves.next()?;
+
+        match self.game.make_move(column) {
+            Ok(()) => Some(Ok(self.game.clone())),
+            Err(e) => {
+                self.failed = true;
+                Some(Err((idx, e)))
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{Classic, Winner};
+
+    const RED_WINS: [Column<7>; 7] = [
+        Column::D,
+        Column::E,
+        Column::D,
+        Column::E,
+        Column::D,
+        Column::E,
+        Column::D,
+    ];
+
+    #[test]
+    fn replays_play_out_to_the_winner() {
+        let game = Classic::replay(&RED_WINS, Colour::Red, false).unwrap();
+        assert_eq!(game.winner(), Some(Winner::Red));
+
+        let positions = Classic::replay_iter(&RED_WINS, Colour::Red, false)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(positions.len(), RED_WINS.len());
+        assert_eq!(positions.last(), Some(&game));
+        assert!(positions[..6].iter().all(|p| p.winner().is_none()));
+    }
+
+    #[test]
+    fn replays_give_the_index_of_the_first_illegal_move() {
+        let moves = [Column::B; 8];
+
+        assert_eq!(
+            Classic::replay(&moves, Colour::Red, false),
+            Err((6, MoveError::ColumnFull(Column::B)))
+        );
+
+        let results = Classic::replay_iter(&moves, Colour::Red, false).collect::<Vec<_>>();
+        assert_eq!(results.len(), 7);
+        assert_eq!(results[6], Err((6, MoveError::ColumnFull(Column::B))));
+    }
+
+    #[test]
+    fn applying_an_illegal_move_leaves_the_game_alone() {
+        let mut game = Classic::new(Colour::Red, false);
+        game.make_move(Column::A).unwrap();
+        let before = game.clone();
+
+        assert_eq!(
+            game.apply_moves(&[Column::A; 6]),
+            Err((5, MoveError::ColumnFull(Column::A)))
+        );
+        assert_eq!(game, before);
+
+        assert_eq!(game.apply_moves(&[Column::C, Column::D]), Ok(()));
+        assert_eq!(game.history().len(), 3);
+    }
+
+    #[test]
+    fn positions_run_from_the_start_to_the_winner() {
+        let positions = Classic::positions(&RED_WINS, Colour::Red, false).collect::<Vec<_>>();
+
+        assert_eq!(positions.len(), RED_WINS.len() + 1);
+        assert_eq!(positions[0], Classic::new(Colour::Red, false));
+        assert_eq!(positions.last().unwrap().winner(), Some(Winner::Red));
+
+        // stopping before an illegal move
+        let moves = [Column::A; 8];
+        assert_eq!(Classic::positions(&moves, Colour::Red, false).count(), 7);
+    }
+
+    #[test]
+    fn an_illegal_third_move_rolls_back_the_first_two() {
+        // flipping, so the rollback has gravity and the round to put back too
+        let mut game = Classic::new(Colour::Red, true);
+        for _ in 0..4 {
+            game.make_move(Column::A).unwrap();
+        }
+        let before = game.clone();
+
+        assert_eq!(
+            game.apply_moves(&[Column::A, Column::A, Column::A]),
+            Err((2, MoveError::ColumnFull(Column::A)))
+        );
+        assert_eq!(game, before);
+        assert_eq!(game.ply(), 4);
+        assert_eq!(game.current_colour(), Colour::Red);
+    }
+}
@@ -0,0 +1,310 @@
+//! Saving games to disk.
+//!
+//! A save holds the settings a game was started with and every move made
+//! since, one `key value` pair per line:
+//!
+//! ```text
+//! first R
+//! gravity down
+//! flipping true
+//! flip-interval 2
+//! flip-policy alternate
+//! win-length 4
+//! swap-rule false
+//! pop-out false
+//! persist-wins true
+//! blocked D2,A5
+//! moves D,D,^D
+//! ```
+//!
+//! The flip policy is `alternate`, `random` followed by the seed, or
+//! `toward` followed by a gravity. Blocked cells are written as their column
+//! and row. Drops are written as their column, pops as their column after a
+//! `^`, and a swap as `swap`.
+//! Loading replays the moves, which recreates the gravity, round and history
+//! exactly, so a loaded game plays and undoes like the one that was saved.
+
+use std::path::Path;
+
+use core::convert::TryFrom;
+
+use crate::{Colour, Column, FlipPolicy, Game, Gravity, Move, MoveError};
+
+#[derive(Debug)]
+pub enum LoadError<const W: usize> {
+    Io(std::io::Error),
+    /// A line that's missing, out of place or can't be read.
+    Invalid(String),
+    /// The index of a saved move that can't be played, and why.
+    IllegalMove(usize, MoveError<W>),
+}
+
+impl<const W: usize> std::fmt::Display for LoadError<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::Invalid(line) => write!(f, "invalid line in save: {:?}", line),
+            LoadError::IllegalMove(idx, e) => write!(f, "saved move {} is illegal: {}", idx, e),
+        }
+    }
+}
+
+impl<const W: usize> std::error::Error for LoadError<W> {}
+
+impl<const W: usize> From<std::io::Error> for LoadError<W> {
+    fn from(e: std::io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+fn gravity_name(gravity: Gravity) -> &'static str {
+    match gravity {
+        Gravity::Down => "down",
+        Gravity::Up => "up",
+        Gravity::Left => "left",
+        Gravity::Right => "right",
+    }
+}
+
+fn policy_name(policy: FlipPolicy) -> String {
+    match policy {
+        FlipPolicy::Alternate => "alternate".to_owned(),
+        FlipPolicy::Random(seed) => format!("random {}", seed),
+        FlipPolicy::Toward(gravity) => format!("toward {}", gravity_name(gravity)),
+    }
+}
+
+fn read_gravity(s: &str) -> Option<Gravity> {
+    [Gravity::Down, Gravity::Up, Gravity::Left, Gravity::Right]
+        .iter()
+        .copied()
+        .find(|&g| gravity_name(g) == s)
+}
+
+fn read_policy(s: &str) -> Option<FlipPolicy> {
+    match s.split_once(' ') {
+        None if s == "alternate" => Some(FlipPolicy::Alternate),
+        Some(("random", seed)) => seed.parse().ok().map(FlipPolicy::Random),
+        Some(("toward", gravity)) => read_gravity(gravity).map(FlipPolicy::Toward),
+        _ => None,
+    }
+}
+
+/// A move the way saves write it, which is also how `--moves` reads them.
+pub fn write_move<const W: usize>(mv: Move<W>) -> String {
+    match mv {
+        Move::Drop(column) => column.to_string(),
+        Move::Pop(column) => format!("^{}", column),
+        Move::Swap => "swap".to_owned(),
+    }
+}
+
+fn read_move<const W: usize>(s: &str) -> Option<Move<W>> {
+    if s == "swap" {
+        return Some(Move::Swap);
+    }
+
+    match s.strip_prefix('^') {
+        Some(column) => column.parse().ok().map(Move::Pop),
+        None => s.parse().ok().map(Move::Drop),
+    }
+}
+
+fn read_cell<const W: usize>(s: &str) -> Option<(Column<W>, u8)> {
+    let mut chars = s.chars();
+    let column = Column::try_from(chars.next()?).ok()?;
+    let row = chars.as_str().parse().ok()?;
+
+    Some((column, row))
+}
+
+impl<const W: usize, const H: usize> Game<W, H> {
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let moves = self
+            .history
+            .iter()
+            .map(|&mv| write_move(mv))
+            .collect::<Vec<_>>()
+            .join(",");
+        let blocked = self
+            .state
+            .iter_cells()
+            .filter(|&(column, row, _)| self.state.is_blocked(column, row))
+            .map(|(column, row, _)| format!("{}{}", column, row))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let save = format!(
+            "first {}\ngravity {}\nflipping {}\nflip-interval {}\nflip-policy {}\nwin-length {}\nswap-rule {}\npop-out {}\npersist-wins {}\nblocked {}\nmoves {}\n",
+            self.first_player,
+            gravity_name(self.start_gravity),
+            self.flipping,
+            self.flip_interval,
+            policy_name(self.flip_policy),
+            self.win_length,
+            self.swap_rule,
+            self.pop_out,
+            self.persist_wins_through_flip,
+            blocked,
+            moves
+        );
+
+        std::fs::write(path, save)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadError<W>> {
+        let save = std::fs::read_to_string(path)?;
+        let mut lines = save.lines();
+
+        let mut field = |key: &str| {
+            let line = lines.next().unwrap_or("");
+
+            match line.split_once(' ') {
+                Some((k, v)) if k == key => Ok((v, line)),
+                _ => Err(LoadError::Invalid(line.to_owned())),
+            }
+        };
+
+        let first = match field("first")? {
+            ("R", _) => Colour::Red,
+            ("Y", _) => Colour::Yellow,
+            (_, line) => return Err(LoadError::Invalid(line.to_owned())),
+        };
+
+        let (gravity, line) = field("gravity")?;
+        let gravity = read_gravity(gravity).ok_or_else(|| LoadError::Invalid(line.to_owned()))?;
+
+        let (flipping, line) = field("flipping")?;
+        let flipping = flipping
+            .parse()
+            .map_err(|_| LoadError::Invalid(line.to_owned()))?;
+
+        let (flip_interval, line) = field("flip-interval")?;
+        let flip_interval = flip_interval
+            .parse()
+            .map_err(|_| LoadError::Invalid(line.to_owned()))?;
+
+        let (flip_policy, line) = field("flip-policy")?;
+        let flip_policy =
+            read_policy(flip_policy).ok_or_else(|| LoadError::Invalid(line.to_owned()))?;
+
+        let (win_length, line) = field("win-length")?;
+        let win_length = win_length
+            .parse()
+            .ok()
+            .filter(|length| (1..=W.max(H)).contains(length))
+            .ok_or_else(|| LoadError::Invalid(line.to_owned()))?;
+
+        let (swap_rule, line) = field("swap-rule")?;
+        let swap_rule = swap_rule
+            .parse()
+            .map_err(|_| LoadError::Invalid(line.to_owned()))?;
+
+        let (pop_out, line) = field("pop-out")?;
+        let pop_out = pop_out
+            .parse()
+            .map_err(|_| LoadError::Invalid(line.to_owned()))?;
+
+        let (persist_wins, line) = field("persist-wins")?;
+        let persist_wins = persist_wins
+            .parse()
+            .map_err(|_| LoadError::Invalid(line.to_owned()))?;
+
+        let (blocked, line) = field("blocked")?;
+        let blocked = blocked
+            .split(',')
+            .filter(|c| !c.is_empty())
+            .map(read_cell)
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| LoadError::Invalid(line.to_owned()))?;
+
+        let (moves, line) = field("moves")?;
+        let mut builder = Self::builder()
+            .first_player(first)
+            .gravity(gravity)
+            .flipping(flipping)
+            .flip_interval(flip_interval)
+            .flip_policy(flip_policy)
+            .win_length(win_length)
+            .swap_rule(swap_rule)
+            .pop_out(pop_out)
+            .persist_wins_through_flip(persist_wins);
+        for &(column, row) in &blocked {
+            builder = builder
+                .block(column, row)
+                .map_err(|_| LoadError::Invalid(line.to_owned()))?;
+        }
+        let mut game = builder.build();
+
+        for (idx, mv) in moves.split(',').filter(|m| !m.is_empty()).enumerate() {
+            let mv = read_move(mv).ok_or_else(|| LoadError::Invalid(line.to_owned()))?;
+            game.make_move_ex(mv)
+                .map_err(|e| LoadError::IllegalMove(idx, e))?;
+        }
+
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Classic, Column};
+
+    use super::*;
+
+    #[test]
+    fn a_game_saved_between_flips_loads_and_plays_on_the_same() {
+        let path = std::env::temp_dir().join(format!("c4-save-test-{}", std::process::id()));
+
+        // a flip every two moves, so this is halfway through a round with
+        // gravity already flipped
+        let mut control = Classic::new(Colour::Yellow, true);
+        for column in [Column::D, Column::D, Column::C] {
+            control.make_move(column).unwrap();
+        }
+        assert_eq!(control.state().gravity(), Gravity::Up);
+
+        control.save(&path).unwrap();
+        let mut loaded = Classic::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, control);
+
+        control.make_move(Column::E).unwrap();
+        loaded.make_move(Column::E).unwrap();
+        assert_eq!(loaded, control);
+
+        while control.undo_move().is_ok() {
+            loaded.undo_move().unwrap();
+            assert_eq!(loaded, control);
+        }
+        assert!(loaded.undo_move().is_err());
+    }
+
+    #[test]
+    fn blocked_cells_are_saved() {
+        let path = std::env::temp_dir().join(format!("c4-save-blocked-{}", std::process::id()));
+
+        let mut control = Classic::builder()
+            .block(Column::D, 2)
+            .unwrap()
+            .block(Column::A, 5)
+            .unwrap()
+            .build();
+        for column in [Column::D, Column::D, Column::D] {
+            control.make_move(column).unwrap();
+        }
+
+        control.save(&path).unwrap();
+        let mut loaded = Classic::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, control);
+        assert!(loaded.state().is_blocked(Column::D, 2));
+        assert!(loaded.state().is_blocked(Column::A, 5));
+
+        // the fourth disc settles above the blocked cell in both
+        control.make_move(Column::D).unwrap();
+        loaded.make_move(Column::D).unwrap();
+        assert_eq!(loaded, control);
+        assert_eq!(loaded.state().piece_at(Column::D, 4), Some(Colour::Yellow));
+    }
+}
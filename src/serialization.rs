@@ -0,0 +1,118 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Board, Colour, Column, Gravity};
+
+/// The serialized form of a `Board`: the discs in each column (or row, under
+/// sideways gravity) listed outward from the wall gravity points at, skipping
+/// blocked cells, which are listed as column and row.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct BoardRepr {
+    columns: Vec<Vec<Colour>>,
+    gravity: Gravity,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    blocked: Vec<(usize, u8)>,
+}
+
+impl<const W: usize, const H: usize> From<Board<W, H>> for BoardRepr {
+    fn from(board: Board<W, H>) -> Self {
+        let columns = (0..board.lane_count())
+            .map(|lane| board.lane_discs(lane))
+            .collect();
+
+        let blocked = board
+            .iter_cells()
+            .filter(|&(column, row, _)| board.is_blocked(column, row))
+            .map(|(column, row, _)| (column.to_idx(), row))
+            .collect();
+
+        Self {
+            columns,
+            gravity: board.gravity,
+            blocked,
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> TryFrom<BoardRepr> for Board<W, H> {
+    type Error = String;
+
+    fn try_from(repr: BoardRepr) -> Result<Self, Self::Error> {
+        let mut board = Board::with_gravity(repr.gravity);
+
+        for (column, row) in repr.blocked {
+            if column >= W || row as usize >= H {
+                return Err(format!("blocked cell {} {} is off the board", column, row));
+            }
+
+            board
+                .block(Column(column), row)
+                .map_err(|e| e.to_string())?;
+        }
+
+        if repr.columns.len() != board.lane_count() {
+            return Err(format!(
+                "expected {} columns, found {}",
+                board.lane_count(),
+                repr.columns.len()
+            ));
+        }
+
+        for (lane, discs) in repr.columns.into_iter().enumerate() {
+            if discs.len() > board.lane_len(lane) {
+                return Err(format!(
+                    "column {} holds more than {} discs",
+                    lane,
+                    board.lane_len(lane)
+                ));
+            }
+
+            board.set_lane(lane, &discs);
+        }
+
+        Ok(board)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::{Board, Classic, ClassicBoard, Colour, Column, Gravity};
+
+    #[test]
+    fn games_come_back_the_same_from_json() {
+        let mut game = Classic::new(Colour::Red, true);
+        for &column in &[Column::D, Column::D, Column::C, Column::E, Column::A] {
+            game.make_move(column).unwrap();
+        }
+
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(serde_json::from_str::<Classic>(&json).unwrap(), game);
+    }
+
+    #[test]
+    fn boards_come_back_the_same_from_json() {
+        let mut board = ClassicBoard::with_gravity(Gravity::Left);
+        board.block(Column::C, 2).unwrap();
+        board.set_lane(0, &[Colour::Red, Colour::Yellow]);
+
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(serde_json::from_str::<ClassicBoard>(&json).unwrap(), board);
+    }
+
+    #[test]
+    fn overfull_columns_are_rejected() {
+        let json = r#"{"columns":[["Red","Red","Red"],[],[]],"gravity":"Down"}"#;
+        let err = serde_json::from_str::<Board<3, 2>>(json).unwrap_err();
+        assert!(
+            err.to_string().contains("holds more than 2 discs"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{Colour, Driver, Game, PlayerKind, Winner};
+
+/// One of the two players in a `Match`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MatchSide {
+    /// Plays red in the first game.
+    A,
+    B,
+}
+
+impl MatchSide {
+    pub fn other(self) -> Self {
+        match self {
+            MatchSide::A => MatchSide::B,
+            MatchSide::B => MatchSide::A,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    /// The game had no winner yet, as when a bot found no move.
+    Unfinished,
+}
+
+impl core::fmt::Display for RecordError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RecordError::Unfinished => write!(f, "the game isn't finished"),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// A best of `best_of` series between two players, who swap colours every
+/// game so that each moves first as often as the other. It ends early once
+/// one of them has won more than half the games.
+#[derive(Debug, Clone)]
+pub struct Match {
+    players: [PlayerKind; 2],
+    best_of: u32,
+    /// The winner of each game played, `None` for a tie.
+    results: Vec<Option<MatchSide>>,
+}
+
+impl Match {
+    pub fn new(a: PlayerKind, b: PlayerKind, best_of: u32) -> Self {
+        Self {
+            players: [a, b],
+            best_of,
+            results: Vec::new(),
+        }
+    }
+
+    /// The colour `side` plays in the next game, before any swap.
+    pub fn colour(&self, side: MatchSide) -> Colour {
+        let a = if self.results.len().is_multiple_of(2) {
+            Colour::Red
+        } else {
+            Colour::Yellow
+        };
+
+        match side {
+            MatchSide::A => a,
+            MatchSide::B => a.invert(),
+        }
+    }
+
+    fn player(&self, side: MatchSide) -> PlayerKind {
+        match side {
+            MatchSide::A => self.players[0],
+            MatchSide::B => self.players[1],
+        }
+    }
+
+    /// A driver for the next game, played on `game`, with each side on its
+    /// colour. `None` once the match is over.
+    pub fn next_game<const W: usize, const H: usize>(
+        &self,
+        game: Game<W, H>,
+    ) -> Option<Driver<W, H>> {
+        if self.is_over() {
+            return None;
+        }
+
+        let red = if self.colour(MatchSide::A) == Colour::Red {
+            MatchSide::A
+        } else {
+            MatchSide::B
+        };
+
+        Some(Driver::new(
+            game,
+            self.player(red),
+            self.player(red.other()),
+        ))
+    }
+
+    /// Counts the result of the finished game started by `next_game`,
+    /// giving the side that won it. Unfinished games aren't counted.
+    pub fn record<const W: usize, const H: usize>(
+        &mut self,
+        game: &Game<W, H>,
+    ) -> Result<Option<MatchSide>, RecordError> {
+        if !game.is_finished() {
+            return Err(RecordError::Unfinished);
+        }
+
+        // after a swap each side ends up playing the other colour
+        let mut a = self.colour(MatchSide::A);
+        if game.swapped() {
+            a = a.invert();
+        }
+
+        let winner = match game.winner() {
+            Some(Winner::Tie) | None => None,
+            Some(w) if w.to_colour() == Some(a) => Some(MatchSide::A),
+            Some(_) => Some(MatchSide::B),
+        };
+        self.results.push(winner);
+
+        Ok(winner)
+    }
+
+    /// The winner of each game played so far, `None` for a tie.
+    pub fn results(&self) -> &[Option<MatchSide>] {
+        &self.results
+    }
+
+    pub fn wins(&self, side: MatchSide) -> u32 {
+        self.results.iter().filter(|&&w| w == Some(side)).count() as u32
+    }
+
+    pub fn ties(&self) -> u32 {
+        self.results.iter().filter(|w| w.is_none()).count() as u32
+    }
+
+    /// Whether one side has won a majority of `best_of`, or every game has
+    /// been played.
+    pub fn is_over(&self) -> bool {
+        self.results.len() as u32 >= self.best_of
+            || [MatchSide::A, MatchSide::B]
+                .iter()
+                .any(|&side| self.wins(side) > self.best_of / 2)
+    }
+
+    /// The side that won the match, once it's over. `None` until then, or
+    /// if both won as many games.
+    pub fn winner(&self) -> Option<MatchSide> {
+        if !self.is_over() {
+            return None;
+        }
+
+        let (a, b) = (self.wins(MatchSide::A), self.wins(MatchSide::B));
+        match a.cmp(&b) {
+            core::cmp::Ordering::Greater => Some(MatchSide::A),
+            core::cmp::Ordering::Less => Some(MatchSide::B),
+            core::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Classic;
+
+    #[test]
+    fn unfinished_games_arent_recorded() {
+        let mut series = Match::new(PlayerKind::Human, PlayerKind::Human, 3);
+        let mut game = Classic::new(Colour::Red, false);
+
+        assert_eq!(series.record(&game), Err(RecordError::Unfinished));
+        assert!(series.results().is_empty());
+
+        game.resign(Colour::Yellow).unwrap();
+        assert_eq!(series.record(&game), Ok(Some(MatchSide::A)));
+        assert_eq!(series.colour(MatchSide::A), Colour::Yellow);
+    }
+
+    #[test]
+    fn a_best_of_three_ends_once_one_side_wins_two() {
+        let mut series = Match::new(PlayerKind::Human, PlayerKind::Human, 3);
+
+        for idx in 0..2 {
+            assert!(!series.is_over());
+            let driver = series.next_game(Classic::new(Colour::Red, false)).unwrap();
+
+            // A plays red then yellow, and wins both by the other resigning
+            let mut game = driver.into_game();
+            let a = series.colour(MatchSide::A);
+            assert_eq!(a, [Colour::Red, Colour::Yellow][idx]);
+            game.resign(a.invert()).unwrap();
+
+            assert_eq!(series.record(&game), Ok(Some(MatchSide::A)));
+        }
+
+        assert!(series.is_over());
+        assert_eq!(series.winner(), Some(MatchSide::A));
+        assert_eq!(series.wins(MatchSide::A), 2);
+        assert_eq!(series.results().len(), 2);
+        assert!(series.next_game(Classic::new(Colour::Red, false)).is_none());
+    }
+}
@@ -0,0 +1,145 @@
+//! Positions drawn as SVG images, for sharing outside a terminal.
+
+use alloc::{format, string::String};
+use core::fmt::Write;
+
+use crate::{Board, Colour, Column, Game};
+
+// the side of each cell, and the radius of the hole or disc in it
+const CELL: usize = 60;
+const RADIUS: usize = 24;
+
+const BOARD_FILL: &str = "#1f4bb4";
+const HOLE_FILL: &str = "#ffffff";
+// blocked cells have no hole, just a darker patch of board
+const BLOCKED_FILL: &str = "#112a66";
+const RED_FILL: &str = "#d7263d";
+const YELLOW_FILL: &str = "#f5d300";
+const HIGHLIGHT_STROKE: &str = "#111111";
+
+impl<const W: usize, const H: usize> Board<W, H> {
+    /// The board as an SVG image, with the top row at the top whichever way
+    /// gravity points.
+    pub fn to_svg(&self) -> String {
+        self.to_svg_highlighting(&[])
+    }
+
+    /// Like `to_svg`, outlining the discs in `cells`, eg. the winning line.
+    pub fn to_svg_highlighting(&self, cells: &[(Column<W>, u8)]) -> String {
+        let (width, height) = (W * CELL, H * CELL);
+        let mut out = String::new();
+
+        writeln!(
+            out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+            w = width,
+            h = height
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "  <rect width=\"{}\" height=\"{}\" rx=\"{}\" fill=\"{}\"/>",
+            width,
+            height,
+            CELL / 6,
+            BOARD_FILL
+        )
+        .unwrap();
+
+        for (column, row, disc) in self.iter_cells() {
+            let x = column.to_idx() * CELL + CELL / 2;
+            // row 0 is the bottom row
+            let y = (H - 1 - row as usize) * CELL + CELL / 2;
+
+            if self.is_blocked(column, row) {
+                writeln!(
+                    out,
+                    "  <rect x=\"{}\" y=\"{}\" width=\"{side}\" height=\"{side}\" fill=\"{}\"/>",
+                    x - RADIUS,
+                    y - RADIUS,
+                    BLOCKED_FILL,
+                    side = 2 * RADIUS
+                )
+                .unwrap();
+                continue;
+            }
+
+            let fill = match disc {
+                Some(Colour::Red) => RED_FILL,
+                Some(Colour::Yellow) => YELLOW_FILL,
+                None => HOLE_FILL,
+            };
+            let stroke = if cells.contains(&(column, row)) {
+                format!(" stroke=\"{}\" stroke-width=\"4\"", HIGHLIGHT_STROKE)
+            } else {
+                String::new()
+            };
+
+            writeln!(
+                out,
+                "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"{}/>",
+                x, y, RADIUS, fill, stroke
+            )
+            .unwrap();
+        }
+
+        out.push_str("</svg>\n");
+
+        out
+    }
+}
+
+impl<const W: usize, const H: usize> Game<W, H> {
+    /// Like `Board::to_svg`, outlining the winning line if there is one.
+    pub fn to_svg(&self) -> String {
+        let line = self
+            .winning_line()
+            .map(|(_, line)| line)
+            .unwrap_or_default();
+
+        self.state().to_svg_highlighting(&line)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{Classic, ClassicBoard};
+
+    #[test]
+    fn every_cell_gets_a_circle() {
+        let mut board = ClassicBoard::from_notation("RY/Y/-/R/-/-/-/d").unwrap();
+        board.block(Column::G, 0).unwrap();
+        let svg = board.to_svg();
+
+        assert!(svg.starts_with("<svg "));
+        assert_eq!(svg.matches("<circle ").count(), 7 * 6 - 1);
+        assert_eq!(svg.matches(RED_FILL).count(), 2);
+        assert_eq!(svg.matches(YELLOW_FILL).count(), 2);
+        assert_eq!(svg.matches(BLOCKED_FILL).count(), 1);
+        // the red disc in A1 is in the bottom left cell
+        assert!(svg.contains(&format!(
+            "<circle cx=\"30\" cy=\"330\" r=\"24\" fill=\"{}\"/>",
+            RED_FILL
+        )));
+    }
+
+    #[test]
+    fn won_games_outline_the_winning_line() {
+        let mut game = Classic::new(Colour::Red, false);
+        for column in [
+            Column::A,
+            Column::B,
+            Column::A,
+            Column::B,
+            Column::A,
+            Column::B,
+            Column::A,
+        ] {
+            game.make_move(column).unwrap();
+        }
+
+        assert_eq!(game.to_svg().matches(HIGHLIGHT_STROKE).count(), 4);
+        assert_eq!(game.state().to_svg().matches(HIGHLIGHT_STROKE).count(), 0);
+    }
+}
@@ -0,0 +1,326 @@
+//! Solved small boards.
+//!
+//! A tablebase holds the value of every position reachable from a starting
+//! game under perfect play, so a bot given one never has to search. Only
+//! boards up to about 5x4 can be solved in reasonable time and memory.
+//!
+//! Saved tablebases start with the board size and win length, followed by
+//! one position a line: the board as hex `Board::to_bytes`, the colour to
+//! move, and its value.
+//!
+//! ```text
+//! size 5x4
+//! win-length 4
+//! <board> <R|Y> <win|loss|tie> <distance>
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{Board, Colour, Column, Game, Winner};
+
+/// How a position ends for the player to move, if both play perfectly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Tie,
+}
+
+/// What a position is worth under perfect play, where the winner wins as
+/// quickly as they can and the loser holds on as long as they can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Value {
+    pub outcome: Outcome,
+    /// How many moves are left before the game ends.
+    pub distance: u8,
+}
+
+impl Value {
+    /// Higher is better for the player to move.
+    fn score(self) -> i32 {
+        let distance = self.distance as i32;
+
+        match self.outcome {
+            Outcome::Win => 1000 - distance,
+            Outcome::Tie => 0,
+            Outcome::Loss => distance - 1000,
+        }
+    }
+
+    /// The value of the move into this position, for the player making it.
+    fn one_move_earlier(self) -> Self {
+        let outcome = match self.outcome {
+            Outcome::Win => Outcome::Loss,
+            Outcome::Loss => Outcome::Win,
+            Outcome::Tie => Outcome::Tie,
+        };
+
+        Self {
+            outcome,
+            distance: self.distance + 1,
+        }
+    }
+}
+
+fn outcome_name(outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Win => "win",
+        Outcome::Loss => "loss",
+        Outcome::Tie => "tie",
+    }
+}
+
+/// Positions are keyed by `Board::canonical_key`, as a position and its
+/// mirror are worth the same, along with the colour to move.
+type Key = (u64, Colour);
+
+#[derive(Debug, Clone)]
+struct Entry<const W: usize, const H: usize> {
+    // kept for saving, as keys are only hashes
+    board: Board<W, H>,
+    value: Value,
+}
+
+/// The value of every position reachable from a game, see the module docs.
+///
+/// Only drops are considered, so games that flip gravity, pop discs or
+/// allow a swap aren't covered.
+#[derive(Debug, Clone)]
+pub struct Tablebase<const W: usize, const H: usize> {
+    win_length: usize,
+    entries: HashMap<Key, Entry<W, H>>,
+}
+
+impl<const W: usize, const H: usize> Tablebase<W, H> {
+    /// Solves every position reachable from `game` by dropping discs.
+    pub fn generate(game: &Game<W, H>) -> Self {
+        assert!(
+            !game.flips(),
+            "tablebases can't be made for games that flip gravity"
+        );
+
+        let mut tablebase = Self {
+            win_length: game.win_length,
+            entries: HashMap::new(),
+        };
+        tablebase.solve(game);
+
+        tablebase
+    }
+
+    fn solve(&mut self, game: &Game<W, H>) -> Value {
+        let key = (game.state.canonical_key(), game.current_colour);
+        if let Some(entry) = self.entries.get(&key) {
+            return entry.value;
+        }
+
+        let end = |outcome| Value {
+            outcome,
+            distance: 0,
+        };
+        let value = match game.winner() {
+            Some(Winner::Tie) => end(Outcome::Tie),
+            Some(w) if w.to_colour() == Some(game.current_colour) => end(Outcome::Win),
+            Some(_) => end(Outcome::Loss),
+            None => game
+                .legal_moves()
+                .into_iter()
+                .map(|column| {
+                    self.solve(&game.with_move(column).unwrap())
+                        .one_move_earlier()
+                })
+                .max_by_key(|value| value.score())
+                .unwrap(),
+        };
+
+        self.entries.insert(
+            key,
+            Entry {
+                board: game.state.clone(),
+                value,
+            },
+        );
+
+        value
+    }
+
+    /// How many positions are solved.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether `game` is one the values hold for.
+    fn covers(&self, game: &Game<W, H>) -> bool {
+        !game.flips() && !game.can_swap() && !game.pop_out && game.win_length == self.win_length
+    }
+
+    /// The value of `game` for the player to move, if it was solved.
+    pub fn lookup(&self, game: &Game<W, H>) -> Option<Value> {
+        if !self.covers(game) {
+            return None;
+        }
+
+        let key = (game.state.canonical_key(), game.current_colour);
+        self.entries.get(&key).map(|entry| entry.value)
+    }
+
+    /// The perfect move for the player to move, winning soonest or losing
+    /// latest, if `game` was solved and isn't over.
+    pub fn best_move(&self, game: &Game<W, H>) -> Option<Column<W>> {
+        if !self.covers(game) {
+            return None;
+        }
+
+        let mut best = None;
+        for column in game.legal_moves() {
+            let value = self
+                .lookup(&game.with_move(column).ok()?)?
+                .one_move_earlier();
+
+            if best.is_none_or(|(_, best): (_, Value)| value.score() > best.score()) {
+                best = Some((column, value));
+            }
+        }
+
+        best.map(|(column, _)| column)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut out = format!("size {}x{}\nwin-length {}\n", W, H, self.win_length);
+
+        for (&(_, colour), entry) in &self.entries {
+            let board = entry
+                .board
+                .to_bytes()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>();
+
+            out.push_str(&format!(
+                "{} {} {} {}\n",
+                board,
+                colour,
+                outcome_name(entry.value.outcome),
+                entry.value.distance
+            ));
+        }
+
+        std::fs::write(path, out)
+    }
+
+    /// Reads a tablebase written by `save`, which must be for this board
+    /// size.
+    pub fn load<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let s = std::fs::read_to_string(path)?;
+
+        Self::parse(&s).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut lines = s.lines().enumerate();
+        let mut header = |key: &str| match lines.next() {
+            Some((_, line)) => line
+                .strip_prefix(key)
+                .and_then(|v| v.strip_prefix(' '))
+                .ok_or_else(|| format!("expected {} but found {:?}", key, line)),
+            None => Err(format!("missing {}", key)),
+        };
+
+        let size = header("size")?;
+        if size != format!("{}x{}", W, H) {
+            return Err(format!("tablebase is for a {} board", size));
+        }
+
+        let win_length = header("win-length")?;
+        let win_length = win_length
+            .parse()
+            .map_err(|_| format!("invalid win length {:?}", win_length))?;
+
+        let mut tablebase = Self {
+            win_length,
+            entries: HashMap::new(),
+        };
+
+        for (idx, line) in lines {
+            let invalid = || format!("line {} is invalid", idx + 1);
+
+            let fields = line.split(' ').collect::<Vec<_>>();
+            let (board, colour, outcome, distance) = match fields[..] {
+                [board, colour, outcome, distance] => (board, colour, outcome, distance),
+                _ => return Err(invalid()),
+            };
+
+            let bytes = (0..board.len())
+                .step_by(2)
+                .map(|i| {
+                    board
+                        .get(i..i + 2)
+                        .and_then(|b| u8::from_str_radix(b, 16).ok())
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(invalid)?;
+            let board = Board::from_bytes(&bytes)
+                .map_err(|e| format!("line {} is invalid: {}", idx + 1, e))?;
+
+            let colour = match colour {
+                "R" => Colour::Red,
+                "Y" => Colour::Yellow,
+                _ => return Err(invalid()),
+            };
+
+            let outcome = [Outcome::Win, Outcome::Loss, Outcome::Tie]
+                .iter()
+                .copied()
+                .find(|&o| outcome_name(o) == outcome)
+                .ok_or_else(invalid)?;
+            let distance = distance.parse().map_err(|_| invalid())?;
+
+            tablebase.entries.insert(
+                (board.canonical_key(), colour),
+                Entry {
+                    board,
+                    value: Value { outcome, distance },
+                },
+            );
+        }
+
+        Ok(tablebase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Fitness;
+
+    /// Checks the value of the empty board against a search of every line to
+    /// its end, where wins and losses are scored by the ply they happen on.
+    fn solves_like_a_search<const W: usize, const H: usize>(win_length: usize) {
+        let game = Game::<W, H>::builder().win_length(win_length).build();
+        let value = Tablebase::generate(&game).lookup(&game).unwrap();
+
+        let fitness = rubot::Bot::new(game.current_colour())
+            .detailed_select(&game, rubot::ToCompletion)
+            .unwrap()
+            .fitness;
+
+        let distance = value.distance as usize;
+        match value.outcome {
+            Outcome::Win => assert_eq!(fitness, Fitness::win_at(distance)),
+            Outcome::Loss => assert_eq!(fitness, Fitness::loss_at(distance)),
+            Outcome::Tie => assert_eq!(fitness, Fitness::TIE),
+        }
+    }
+
+    #[test]
+    fn tablebase_values_match_a_full_search() {
+        solves_like_a_search::<3, 3>(3);
+        solves_like_a_search::<4, 3>(3);
+        solves_like_a_search::<4, 4>(3);
+    }
+}
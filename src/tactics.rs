@@ -0,0 +1,132 @@
+use alloc::vec::Vec;
+
+use crate::{Colour, Column, Game, Gravity};
+
+impl<const W: usize, const H: usize> Game<W, H> {
+    /// The columns that win the game for the current player straight away.
+    pub fn winning_moves(&self) -> Vec<Column<W>> {
+        self.legal_moves()
+            .into_iter()
+            .filter(|&c| matches!(self.with_move(c), Ok(g) if g.is_won_by(self.current_colour)))
+            .collect()
+    }
+
+    /// The columns that leave the opponent without a winning move, if some
+    /// other column would give them one.
+    ///
+    /// Moves are played out in full, so flips count both for and against.
+    pub fn blocking_moves(&self) -> Vec<Column<W>> {
+        let mut threatened = false;
+
+        let blocks = self
+            .legal_moves()
+            .into_iter()
+            .filter(|&c| {
+                let next = match self.with_move(c) {
+                    Ok(next) => next,
+                    Err(_) => return false,
+                };
+
+                let blocked = !next.is_won_by(self.current_colour.invert())
+                    && next.winning_moves().is_empty();
+
+                threatened |= !blocked;
+                blocked
+            })
+            .collect();
+
+        if threatened {
+            blocks
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// The empty cells that would complete a line for `colour` if it had a
+    /// disc there, whether or not a disc can be dropped there yet.
+    pub fn threats(&self, colour: Colour) -> Vec<(Column<W>, u8)> {
+        self.state
+            .iter_cells()
+            .filter(|&(_, _, disc)| disc.is_none())
+            .filter(|&(column, row, _)| {
+                let mut game = self.clone();
+                game.state.set_cell(column, row, Some(colour));
+                game.check_win(column, row).is_some()
+            })
+            .map(|(column, row, _)| (column, row))
+            .collect()
+    }
+
+    /// Who should win the fight over the last free cells, by the usual rule
+    /// that the first player wants threats on odd rows and the second on
+    /// even ones, counting rows from 1 at the bottom. `None` if neither or
+    /// both have a threat on their rows, or if gravity ever changes.
+    pub fn parity_advantage(&self) -> Option<Colour> {
+        if self.flips() || self.state.gravity() != Gravity::Down {
+            return None;
+        }
+
+        let first = self.first_player;
+        let second = first.invert();
+
+        // the first row is row 0
+        let odd = self.threats(first).iter().any(|&(_, row)| row % 2 == 0);
+        let even = self.threats(second).iter().any(|&(_, row)| row % 2 == 1);
+
+        match (odd, even) {
+            (true, false) => Some(first),
+            (false, true) => Some(second),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::*;
+    use crate::{Board, Classic};
+
+    fn play(moves: &str) -> Classic {
+        let mut game = Classic::new(Colour::Red, false);
+        for column in moves.chars() {
+            game.make_move(Column::try_from(column).unwrap()).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn winning_moves_complete_a_line() {
+        assert_eq!(play("CCEEAGAGAC").winning_moves(), vec![Column::A]);
+        assert_eq!(play("DD").winning_moves(), vec![]);
+    }
+
+    #[test]
+    fn blocking_moves_stop_the_opponents_win() {
+        // red's three up A
+        assert_eq!(play("AGAGA").blocking_moves(), vec![Column::A]);
+
+        // nothing to block
+        assert_eq!(play("D").blocking_moves(), vec![]);
+    }
+
+    #[test]
+    fn odd_threats_favour_the_first_player() {
+        // red's three along the third row waits on D3, on an odd row
+        // counting from 1, and the diagonal from A1 on D4
+        let position = Board::from_notation("RYR/YRR/RYR/-/-/YY/Y/d").unwrap();
+        let game = Classic::builder().position(position.clone()).build();
+
+        assert_eq!(
+            game.threats(Colour::Red),
+            vec![(Column::D, 2), (Column::D, 3)]
+        );
+        assert_eq!(game.threats(Colour::Yellow), vec![]);
+        assert_eq!(game.parity_advantage(), Some(Colour::Red));
+
+        // but only while gravity stays put
+        let game = Classic::builder().flipping(true).position(position).build();
+        assert_eq!(game.parity_advantage(), None);
+    }
+}
@@ -0,0 +1,125 @@
+//! Bindings for playing from JavaScript, built with the `wasm` feature.
+
+use wasm_bindgen::prelude::*;
+
+use crate::colour_name;
+use crate::{Classic, Colour, Column, MoveError, Winner, BOARD_HEIGHT, BOARD_WIDTH};
+
+#[wasm_bindgen]
+extern "C" {
+    // `std::time::Instant` panics in the browser
+    #[wasm_bindgen(js_namespace = Date)]
+    fn now() -> f64;
+}
+
+#[wasm_bindgen]
+pub struct WasmGame {
+    game: Classic,
+}
+
+/// Stops a search once `deadline` has passed, checking as it goes rather
+/// than only between depths.
+struct Deadline {
+    deadline: f64,
+}
+
+impl rubot::RunCondition for Deadline {
+    fn step(&mut self) -> bool {
+        now() < self.deadline
+    }
+
+    fn depth(&mut self, _: u32) -> bool {
+        self.step()
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// `first_player` is either `"red"` or `"yellow"`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(first_player: &str, flipping: bool) -> Result<WasmGame, JsValue> {
+        let first_player = match first_player {
+            "red" => Colour::Red,
+            "yellow" => Colour::Yellow,
+            _ => return Err(JsValue::from_str("the first player must be red or yellow")),
+        };
+
+        Ok(WasmGame {
+            game: Classic::new(first_player, flipping),
+        })
+    }
+
+    pub fn make_move(&mut self, col: u8) -> Result<(), JsValue> {
+        let column =
+            Column::new(col as usize).ok_or_else(|| JsValue::from_str("no such column"))?;
+
+        self.game
+            .make_move(column)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    pub fn legal_moves(&self) -> Vec<u8> {
+        self.game
+            .legal_moves()
+            .into_iter()
+            .map(|c| c.to_idx() as u8)
+            .collect()
+    }
+
+    /// The legal columns as bits, column 0 being the lowest.
+    pub fn legal_move_mask(&self) -> u8 {
+        self.game.legal_move_mask()
+    }
+
+    /// Every cell row by row from the top, `1` for red, `0` for yellow and
+    /// `-1` if empty.
+    pub fn render_cells(&self) -> Vec<i8> {
+        let board = self.game.state();
+
+        (0..BOARD_HEIGHT as u8)
+            .rev()
+            .flat_map(|row| Column::all().map(move |c| (c, row)))
+            .map(|(c, row)| match board.piece_at(c, row) {
+                Some(Colour::Red) => 1,
+                Some(Colour::Yellow) => 0,
+                None => -1,
+            })
+            .collect()
+    }
+
+    pub fn current_player(&self) -> String {
+        colour_name(self.game.current_colour()).to_ascii_lowercase()
+    }
+
+    /// `"red"`, `"yellow"` or `"tie"` once the game is over.
+    pub fn winner(&self) -> Option<String> {
+        let winner = match self.game.winner()? {
+            Winner::Tie => "tie",
+            w => colour_name(w.to_colour()?),
+        };
+
+        Some(winner.to_ascii_lowercase())
+    }
+
+    /// Plays a move for the current player, returning its column.
+    ///
+    /// The search deepens until `think_ms` has passed, then plays the best
+    /// move it has found so far.
+    pub fn bot_move(&mut self, think_ms: u32) -> Result<u8, JsValue> {
+        if self.game.is_finished() {
+            return Err(JsValue::from_str(
+                &MoveError::<BOARD_WIDTH>::GameOver.to_string(),
+            ));
+        }
+
+        let condition = Deadline {
+            deadline: now() + think_ms as f64,
+        };
+        let column = rubot::Bot::new(self.game.current_colour())
+            .select(&self.game, condition)
+            .ok_or_else(|| JsValue::from_str("no move found"))?;
+        self.make_move(column.to_idx() as u8)?;
+
+        Ok(column.to_idx() as u8)
+    }
+}
@@ -0,0 +1,119 @@
+//! Runs the binary the way a script would, checking its output and exit
+//! codes.
+
+#![cfg(feature = "std")]
+
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_c4"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn json_summaries_give_the_winner_and_moves() {
+    let output = run(&["--moves", "A,B,A,B,A,B,A", "--format", "json"]);
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"winner\":\"Red\",\"moves\":[\"A\",\"B\",\"A\",\"B\",\"A\",\"B\",\"A\"]}\n"
+    );
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn unfinished_games_have_no_winner() {
+    let output = run(&["--moves", "D,D", "--format", "json"]);
+
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "{\"winner\":null,\"moves\":[\"D\",\"D\"]}\n"
+    );
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn bad_arguments_arent_mistaken_for_a_yellow_win() {
+    assert_eq!(run(&["--format", "xml"]).status.code(), Some(3));
+    assert_eq!(
+        run(&["--moves", "A,Z", "--format", "json"]).status.code(),
+        Some(3)
+    );
+}
+
+#[test]
+fn bad_moves_are_reported_by_their_index() {
+    let output = run(&["--moves", "A,A,A,A,A,A,A"]);
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "move 6 (A) is illegal: column A is full\n"
+    );
+    assert_eq!(output.status.code(), Some(3));
+
+    let output = run(&["--moves", "D,Z,D"]);
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "move 1 is invalid: \"Z\" is not a column\n"
+    );
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn tournament_totals_add_up() {
+    let output = run(&[
+        "tournament",
+        "--games",
+        "2",
+        "--a-difficulty",
+        "easy",
+        "--b-difficulty",
+        "easy",
+        "--seed",
+        "44",
+    ]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let tallies = stdout
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let numbers = &fields[fields.len() - 3..];
+            numbers
+                .iter()
+                .map(|n| n.parse::<u32>().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(tallies.len(), 4, "{}", stdout);
+
+    // every row counts both games, and each side's wins are the other's
+    // losses, by bot and by colour
+    for tally in &tallies {
+        assert_eq!(tally.iter().sum::<u32>(), 2, "{}", stdout);
+    }
+    for (a, b) in [(0, 1), (2, 3)] {
+        assert_eq!(tallies[a][0], tallies[b][1], "{}", stdout);
+        assert_eq!(tallies[a][2], tallies[b][2], "{}", stdout);
+    }
+}
+
+#[test]
+fn selfplay_gives_a_record_per_position() {
+    let output = run(&["selfplay", "--games", "1", "--difficulty", "easy"]);
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let records = stdout.lines().collect::<Vec<_>>();
+
+    // the final position's cells, which hold a disc for every move
+    let last = records.last().unwrap();
+    let cells = &last[last.find('[').unwrap() + 1..last.find(']').unwrap()];
+    let pieces = cells.split(',').filter(|&c| c != "0").count();
+
+    assert_eq!(records.len(), pieces + 1, "{}", stdout);
+    assert!(last.ends_with("\"move\":null}"), "{}", last);
+}
@@ -0,0 +1,50 @@
+//! Builds the crate without `std`, run with `cargo test --features testing`.
+//!
+//! Only the rlib is built, as the `cdylib` for wasm can't link without a
+//! panic handler, which is left to whatever embeds the crate.
+
+#![cfg(feature = "testing")]
+
+use std::path::Path;
+use std::process::Command;
+
+fn build(features: &str) {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+
+    let status = Command::new(env!("CARGO"))
+        .current_dir(manifest_dir)
+        .args([
+            "rustc",
+            "--lib",
+            "--crate-type",
+            "rlib",
+            "--no-default-features",
+        ])
+        .args(["--features", features])
+        // a separate target dir so this doesn't wait on the build running it
+        .arg("--target-dir")
+        .arg(manifest_dir.join("target").join("no_std"))
+        .status()
+        .unwrap();
+
+    assert!(
+        status.success(),
+        "building with features {:?} failed",
+        features
+    );
+}
+
+#[test]
+fn the_core_builds_without_an_allocator() {
+    build("");
+}
+
+#[test]
+fn the_core_builds_with_only_alloc_and_serde() {
+    build("alloc,serde");
+}
+
+#[test]
+fn the_core_builds_with_only_serde() {
+    build("serde");
+}
@@ -0,0 +1,41 @@
+//! Drives the binary's `--stdin` mode the way an outside controller would.
+
+#![cfg(feature = "std")]
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn play(input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_c4"))
+        .arg("--stdin")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn piped_moves_play_out_to_a_win() {
+    // red stacks column A while yellow answers in B, with a typo on the way
+    let output = play("A B\nA x B A\nB A\n");
+
+    assert!(output.contains("\"x\" is not a column"), "{}", output);
+    assert_eq!(output.lines().last(), Some("Red wins"), "{}", output);
+}
+
+#[test]
+fn moves_after_the_game_ends_are_ignored() {
+    assert_eq!(play("A B A B A B A B\n"), play("A B A B A B A\n"));
+}
@@ -0,0 +1,43 @@
+//! Plays through the JavaScript bindings in node, run with
+//! `cargo test --target wasm32-unknown-unknown --features wasm` with
+//! `wasm-bindgen-test-runner` set as the target's runner.
+
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use c4::WasmGame;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test]
+fn a_scripted_game_plays_to_a_win() {
+    let mut game = WasmGame::new("yellow", false).unwrap();
+    assert_eq!(game.current_player(), "yellow");
+    assert_eq!(game.legal_moves(), vec![0, 1, 2, 3, 4, 5, 6]);
+
+    // yellow stacks column A while red answers in B
+    for &col in &[0, 1, 0, 1, 0, 1] {
+        game.make_move(col).unwrap();
+        assert_eq!(game.winner(), None);
+    }
+    assert!(game.make_move(7).is_err());
+    game.make_move(0).unwrap();
+
+    assert_eq!(game.winner(), Some("yellow".to_owned()));
+    assert!(game.make_move(3).is_err());
+    assert!(game.bot_move(10).is_err());
+
+    // the bottom row is the last of the seven, and A holds four yellows
+    let cells = game.render_cells();
+    assert_eq!(cells.len(), 42);
+    assert_eq!(cells[35..], [0, 1, -1, -1, -1, -1, -1]);
+    assert_eq!(cells[14], 0);
+    assert_eq!(cells[7], -1);
+}
+
+#[wasm_bindgen_test]
+fn the_bot_answers_within_its_think_time() {
+    let mut game = WasmGame::new("red", false).unwrap();
+
+    let col = game.bot_move(50).unwrap();
+    assert!(col < 7);
+    assert_eq!(game.current_player(), "yellow");
+}